@@ -0,0 +1,113 @@
+// dearrow-cli - program to view and vote for DeArrow submissions
+// Copyright (C) 2024  mschae23
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! The internal API of DeArrow Browser.
+//!
+//! Note that this API may change at any time without notice.
+
+use dearrow_browser_api::string::{ApiThumbnail, ApiTitle, ApiWarning};
+use reqwest::Url;
+use serde::de::DeserializeOwned;
+
+use crate::cache::{Cache, Endpoint};
+use crate::video::VideoId;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum WarningKind {
+    /// View warnings issued by this user.
+    Issued,
+    /// View warnings received by this user.
+    Received,
+}
+
+impl WarningKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Issued => "issued",
+            Self::Received => "received",
+        }
+    }
+}
+
+/// A client for the internal API of a DeArrow Browser instance.
+#[derive(Clone, Debug)]
+pub struct BrowserApiClient {
+    client: reqwest::blocking::Client,
+    base: Url,
+    cache: Cache,
+}
+
+impl BrowserApiClient {
+    /// Creates a new client. `base` will be concatenated with the API path to form the final URI used,
+    /// so the trailing slash is significant.
+    pub fn new(client: reqwest::blocking::Client, base: &str) -> anyhow::Result<Self> {
+        Ok(BrowserApiClient {
+            client,
+            base: super::parse_base(base)?,
+            cache: Cache::disabled(),
+        })
+    }
+
+    /// Caches all responses in `cache`.
+    pub fn with_cache(self, cache: Cache) -> Self {
+        BrowserApiClient {
+            cache,
+            ..self
+        }
+    }
+
+    pub fn base(&self) -> &Url {
+        &self.base
+    }
+
+    /// Gets all titles submitted on a video, in the order returned by the server.
+    pub fn titles_by_video(&self, video: &VideoId) -> anyhow::Result<Vec<ApiTitle>> {
+        self.get_json(self.url(&["titles", "video_id", video.as_str()])?)
+    }
+
+    /// Gets all thumbnails submitted on a video, in the order returned by the server.
+    pub fn thumbnails_by_video(&self, video: &VideoId) -> anyhow::Result<Vec<ApiThumbnail>> {
+        self.get_json(self.url(&["thumbnails", "video_id", video.as_str()])?)
+    }
+
+    /// Gets all titles submitted by a user, in the order returned by the server.
+    pub fn titles_by_user(&self, user: &str) -> anyhow::Result<Vec<ApiTitle>> {
+        self.get_json(self.url(&["titles", "user_id", user])?)
+    }
+
+    /// Gets all thumbnails submitted by a user, in the order returned by the server.
+    pub fn thumbnails_by_user(&self, user: &str) -> anyhow::Result<Vec<ApiThumbnail>> {
+        self.get_json(self.url(&["thumbnails", "user_id", user])?)
+    }
+
+    /// Gets the warnings issued or received by a user.
+    pub fn warnings_by_user(&self, user: &str, kind: WarningKind) -> anyhow::Result<Vec<ApiWarning>> {
+        self.get_json(self.url(&["warnings", "user_id", user, kind.name()])?)
+    }
+
+    fn get_json<T: DeserializeOwned>(&self, url: Url) -> anyhow::Result<T> {
+        super::get_json(&self.client, &self.cache, Endpoint::Browser, url)
+    }
+
+    fn url(&self, segments: &[&str]) -> anyhow::Result<Url> {
+        let mut url = self.base.clone();
+        url.path_segments_mut().map_err(|()| anyhow::anyhow!("API URI cannot be a base: {}", self.base))?
+            .pop_if_empty()
+            .extend(segments);
+        Ok(url)
+    }
+}
@@ -0,0 +1,76 @@
+// dearrow-cli - program to view and vote for DeArrow submissions
+// Copyright (C) 2024  mschae23
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//! The API of an Invidious instance, used as an alternative source of original titles.
+
+use anyhow::Context;
+use reqwest::Url;
+use serde::Deserialize;
+
+use crate::cache::{Cache, Endpoint};
+use crate::original_title::OriginalTitleProvider;
+use crate::video::VideoId;
+
+#[derive(Deserialize)]
+struct InvidiousVideo {
+    title: String,
+}
+
+/// A client for the API of an Invidious instance, or anything compatible with its `/api/v1/videos` endpoint.
+#[derive(Clone, Debug)]
+pub struct InvidiousClient {
+    client: reqwest::blocking::Client,
+    base: Url,
+    cache: Cache,
+}
+
+impl InvidiousClient {
+    /// Creates a new client. `base` will be concatenated with the API path (`api/v1/...`) to form the final URI used,
+    /// so the trailing slash is significant.
+    pub fn new(client: reqwest::blocking::Client, base: &str) -> anyhow::Result<Self> {
+        Ok(InvidiousClient {
+            client,
+            base: super::parse_base(base)?,
+            cache: Cache::disabled(),
+        })
+    }
+
+    /// Caches all responses in `cache`.
+    pub fn with_cache(self, cache: Cache) -> Self {
+        InvidiousClient {
+            cache,
+            ..self
+        }
+    }
+
+    pub fn base(&self) -> &Url {
+        &self.base
+    }
+
+    /// Gets the current title of a video.
+    pub fn get_original_title(&self, video: &VideoId) -> anyhow::Result<String> {
+        let mut url = self.base.join("api/v1/videos/")?.join(video.as_str())?;
+        url.query_pairs_mut().append_pair("fields", "title");
+
+        let response: InvidiousVideo = super::get_json(&self.client, &self.cache, Endpoint::Invidious, url).context("Failed to get Invidious response")?;
+        Ok(response.title)
+    }
+}
+
+impl OriginalTitleProvider for InvidiousClient {
+    fn original_title(&self, video: &VideoId) -> anyhow::Result<Option<String>> {
+        self.get_original_title(video).map(Some)
+    }
+}
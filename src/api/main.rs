@@ -0,0 +1,473 @@
+// dearrow-cli - program to view and vote for DeArrow submissions
+// Copyright (C) 2024  mschae23
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! The DeArrow API of SponsorBlockServer.
+
+use std::fmt::{Display, Formatter};
+use anyhow::{Context, bail};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::cache::{Cache, Endpoint};
+use crate::video::VideoId;
+
+#[derive(Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct MainApiTitle {
+    pub title: String,
+    pub original: bool,
+    pub votes: i32,
+    pub locked: bool,
+    #[serde(rename = "UUID")]
+    pub uuid: String,
+    #[serde(rename = "userID")]
+    pub user_id: String,
+}
+
+#[derive(Deserialize, Clone, PartialEq, Debug)]
+pub struct MainApiThumbnail {
+    pub timestamp: Option<f64>,
+    pub original: bool,
+    pub votes: i32,
+    pub locked: bool,
+    #[serde(rename = "UUID")]
+    pub uuid: String,
+    #[serde(rename = "userID")]
+    pub user_id: String,
+}
+
+#[derive(Deserialize, Clone, PartialEq, Debug)]
+pub struct MainApiCasualVote {
+    pub id: CasualCategory,
+    pub count: i32,
+}
+
+#[derive(Deserialize, Clone, PartialEq, Debug)]
+pub struct MainApiResponse {
+    pub titles: Vec<MainApiTitle>,
+    pub thumbnails: Vec<MainApiThumbnail>,
+    #[serde(rename = "casualVotes")]
+    pub casual_votes: Vec<MainApiCasualVote>,
+
+    #[serde(rename = "randomTime")]
+    pub random_time: Option<f64>,
+    #[serde(rename = "videoDuration")]
+    pub video_duration: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum CasualCategory {
+    #[serde(rename = "funny")]
+    Funny,
+    #[serde(rename = "creative")]
+    Creative,
+    #[serde(rename = "clever")]
+    Clever,
+    #[serde(rename = "descriptive")]
+    Descriptive,
+    #[serde(rename = "other")]
+    Other,
+}
+
+impl CasualCategory {
+    pub fn name(self) -> &'static str {
+        match self {
+            CasualCategory::Funny => "funny",
+            CasualCategory::Creative => "creative",
+            CasualCategory::Clever => "clever",
+            CasualCategory::Descriptive => "descriptive",
+            CasualCategory::Other => "other",
+        }
+    }
+}
+
+/// The body of a request to submit or vote for a title or thumbnail.
+#[derive(Serialize, Clone, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BrandingVote {
+    pub service: String,
+    pub user_agent: String,
+    /// The *private* user ID of the voter.
+    #[serde(rename = "userID")]
+    pub user_id: String,
+    #[serde(rename = "videoID")]
+    pub video_id: String,
+    #[serde(flatten)]
+    pub submission: BrandingSubmission,
+    pub downvote: bool,
+    /// Only has an effect for VIP users.
+    pub auto_lock: bool,
+    /// Only has an effect for titles.
+    pub was_warned: bool,
+    pub casual_mode: bool,
+}
+
+/// The title or thumbnail a [`BrandingVote`] is for.
+///
+/// Serialized as a `title` or `thumbnail` field of the request body.
+#[derive(Serialize, Clone, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum BrandingSubmission {
+    Title {
+        title: String,
+    },
+    Thumbnail {
+        original: bool,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        timestamp: Option<f64>,
+    },
+}
+
+impl BrandingVote {
+    /// Creates an upvote with auto-lock enabled.
+    pub fn new(user_id: String, video_id: String, submission: BrandingSubmission) -> Self {
+        BrandingVote {
+            service: String::from("YouTube"),
+            user_agent: String::from(crate::USER_AGENT),
+            user_id,
+            video_id,
+            submission,
+            downvote: false,
+            auto_lock: true,
+            was_warned: false,
+            casual_mode: false,
+        }
+    }
+
+    pub fn title(user_id: String, video_id: String, title: String) -> Self {
+        Self::new(user_id, video_id, BrandingSubmission::Title { title, })
+    }
+
+    /// `None` stands for the original thumbnail.
+    pub fn thumbnail(user_id: String, video_id: String, timestamp: Option<f64>) -> Self {
+        Self::new(user_id, video_id, BrandingSubmission::Thumbnail { original: timestamp.is_none(), timestamp, })
+    }
+}
+
+/// The body of a request to vote for casual categories.
+#[derive(Serialize, Clone, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CasualVote {
+    pub service: String,
+    pub user_agent: String,
+    /// The *private* user ID of the voter.
+    #[serde(rename = "userID")]
+    pub user_id: String,
+    #[serde(rename = "videoID")]
+    pub video_id: String,
+    pub downvote: bool,
+    /// Empty when downvoting.
+    pub categories: Vec<CasualCategory>,
+}
+
+impl CasualVote {
+    pub fn new(user_id: String, video_id: String, categories: Vec<CasualCategory>) -> Self {
+        CasualVote {
+            service: String::from("YouTube"),
+            user_agent: String::from(crate::USER_AGENT),
+            user_id,
+            video_id,
+            downvote: false,
+            categories,
+        }
+    }
+
+    pub fn downvote(user_id: String, video_id: String) -> Self {
+        CasualVote {
+            downvote: true,
+            ..Self::new(user_id, video_id, Vec::new())
+        }
+    }
+}
+
+/// The extension a warning (tip) applies to.
+///
+/// Serialized as the number used by SponsorBlockServer.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum WarningType {
+    #[cfg_attr(feature = "cli", value(name = "sponsorblock"))]
+    SponsorBlock,
+    #[cfg_attr(feature = "cli", value(name = "dearrow"))]
+    DeArrow,
+}
+
+impl Serialize for WarningType {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(match self {
+            WarningType::SponsorBlock => 0,
+            WarningType::DeArrow => 1,
+        })
+    }
+}
+
+/// The body of a request to issue or revoke a warning (tip). Only VIP users can do this.
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct WarningRequest {
+    /// The *private* user ID of the issuer.
+    #[serde(rename = "issuerUserID")]
+    pub issuer_user_id: String,
+    /// The *public* user ID of the warned user.
+    #[serde(rename = "userID")]
+    pub user_id: String,
+    /// `false` revokes the user's active warnings.
+    pub enabled: bool,
+    pub reason: String,
+    #[serde(rename = "type")]
+    pub warning_type: WarningType,
+}
+
+/// A kind of DeArrow submission, as used in shadowban requests.
+#[derive(Serialize, Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "lowercase")]
+pub enum DeArrowType {
+    Title,
+    Thumbnail,
+}
+
+/// The parameters of a request to shadowban or unban a user. Only VIP users can do this.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ShadowBanRequest {
+    /// The *private* user ID of the VIP.
+    pub admin_user_id: String,
+    /// The *public* user ID of the user to (un)ban.
+    pub user_id: String,
+    /// `false` unbans the user.
+    pub enabled: bool,
+    /// Whether to hide (when banning) or unhide (when unbanning) the user's existing submissions, as selected by
+    /// `dearrow_types` and `categories`.
+    pub unhide_old_submissions: bool,
+    /// The kinds of the user's existing DeArrow submissions to hide or unhide.
+    pub dearrow_types: Vec<DeArrowType>,
+    /// The SponsorBlock categories of the user's existing segments to hide or unhide.
+    /// `None` uses the server's default (all categories).
+    pub categories: Option<Vec<String>>,
+}
+
+#[derive(Deserialize, Clone, PartialEq, Eq, Debug)]
+struct IsUserVipResponse {
+    vip: bool,
+}
+
+/// What the private user ID is replaced with in a [`RequestPreview`].
+pub const REDACTED: &str = "REDACTED";
+
+/// Derives the public user ID from a private one, like SponsorBlockServer does:
+/// the hex-encoded SHA-256 hash, applied 5000 times.
+pub fn public_user_id(private_user_id: &str) -> String {
+    let mut hash = private_user_id.to_owned();
+
+    for _ in 0..5000 {
+        hash = Sha256::digest(hash.as_bytes()).iter().map(|byte| format!("{:02x}", byte)).collect();
+    }
+
+    hash
+}
+
+/// A request that is printed instead of sent (`--dry-run`), with the private user ID replaced by [`REDACTED`].
+#[derive(Clone, Debug)]
+pub struct RequestPreview {
+    pub method: reqwest::Method,
+    pub url: Url,
+    /// `None` for requests that only have query parameters.
+    pub body: Option<serde_json::Value>,
+    /// The public user ID derived from the redacted private one.
+    pub public_user_id: String,
+}
+
+impl Display for RequestPreview {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.method, self.url)?;
+
+        if let Some(body) = &self.body {
+            write!(f, "\n{}", serde_json::to_string_pretty(body).map_err(|_| std::fmt::Error)?)?;
+        }
+
+        write!(f, "\n(private user ID redacted, public user ID: {})", self.public_user_id)
+    }
+}
+
+/// A client for the DeArrow API of a SponsorBlockServer instance.
+#[derive(Clone, Debug)]
+pub struct MainApiClient {
+    client: reqwest::blocking::Client,
+    base: Url,
+    cache: Cache,
+}
+
+impl MainApiClient {
+    /// Creates a new client. `base` will be concatenated with the API path to form the final URI used,
+    /// so the trailing slash is significant.
+    pub fn new(client: reqwest::blocking::Client, base: &str) -> anyhow::Result<Self> {
+        Ok(MainApiClient {
+            client,
+            base: super::parse_base(base)?,
+            cache: Cache::disabled(),
+        })
+    }
+
+    /// Caches the responses of [`get_branding`](Self::get_branding) in `cache`.
+    /// Votes sent with this client remove the cached branding data of their video.
+    pub fn with_cache(self, cache: Cache) -> Self {
+        MainApiClient {
+            cache,
+            ..self
+        }
+    }
+
+    pub fn base(&self) -> &Url {
+        &self.base
+    }
+
+    /// Gets all titles and thumbnails submitted on a video, including user IDs.
+    pub fn get_branding(&self, video: &VideoId) -> anyhow::Result<MainApiResponse> {
+        super::get_json(&self.client, &self.cache, Endpoint::Branding, self.branding_url(video.as_str())?)
+    }
+
+    /// Checks whether the user with the given *private* user ID is a VIP. The result is never cached.
+    pub fn is_user_vip(&self, private_user_id: &str) -> anyhow::Result<bool> {
+        let mut url = self.base.join("isUserVIP")?;
+        url.query_pairs_mut().append_pair("userID", private_user_id);
+
+        let response = self.client.get(url)
+            .header("User-Agent", crate::USER_AGENT)
+            .send().context("Failed to send VIP status request")?;
+
+        // The URL is not part of the error, since it contains the private user ID
+        if response.status() != 200 {
+            bail!("VIP status request failed. Response: {}", response.status());
+        }
+
+        Ok(response.json::<IsUserVipResponse>().context("Failed to deserialize response")?.vip)
+    }
+
+    /// Submits or votes for a title or thumbnail.
+    ///
+    /// The response is returned as-is, without checking its status.
+    pub fn post_branding(&self, body: &BrandingVote) -> anyhow::Result<reqwest::blocking::Response> {
+        self.invalidate_branding(&body.video_id)?;
+        self.post("branding", body).context("Failed to send branding request")
+    }
+
+    /// Votes for casual categories.
+    ///
+    /// The response is returned as-is, without checking its status.
+    pub fn post_casual(&self, body: &CasualVote) -> anyhow::Result<reqwest::blocking::Response> {
+        self.invalidate_branding(&body.video_id)?;
+        self.post("casual", body).context("Failed to send casual request")
+    }
+
+    /// Issues or revokes a warning (tip).
+    ///
+    /// The response is returned as-is, without checking its status.
+    pub fn post_warning(&self, body: &WarningRequest) -> anyhow::Result<reqwest::blocking::Response> {
+        self.post("warnUser", body).context("Failed to send warning request")
+    }
+
+    /// Shadowbans or unbans a user.
+    ///
+    /// The response is returned as-is, without checking its status.
+    pub fn post_shadow_ban(&self, request: &ShadowBanRequest) -> anyhow::Result<reqwest::blocking::Response> {
+        self.client.post(self.shadow_ban_url(request, &request.admin_user_id)?)
+            .header("User-Agent", crate::USER_AGENT)
+            .send().context("Failed to send shadowban request")
+    }
+
+    /// The request [`post_branding`](Self::post_branding) would send.
+    pub fn preview_branding(&self, body: &BrandingVote) -> anyhow::Result<RequestPreview> {
+        self.preview("branding", body, "userID", &body.user_id)
+    }
+
+    /// The request [`post_casual`](Self::post_casual) would send.
+    pub fn preview_casual(&self, body: &CasualVote) -> anyhow::Result<RequestPreview> {
+        self.preview("casual", body, "userID", &body.user_id)
+    }
+
+    /// The request [`post_warning`](Self::post_warning) would send.
+    pub fn preview_warning(&self, body: &WarningRequest) -> anyhow::Result<RequestPreview> {
+        self.preview("warnUser", body, "issuerUserID", &body.issuer_user_id)
+    }
+
+    /// The request [`post_shadow_ban`](Self::post_shadow_ban) would send.
+    pub fn preview_shadow_ban(&self, request: &ShadowBanRequest) -> anyhow::Result<RequestPreview> {
+        Ok(RequestPreview {
+            method: reqwest::Method::POST,
+            url: self.shadow_ban_url(request, REDACTED)?,
+            body: None,
+            public_user_id: public_user_id(&request.admin_user_id),
+        })
+    }
+
+    fn shadow_ban_url(&self, request: &ShadowBanRequest, admin_user_id: &str) -> anyhow::Result<Url> {
+        let mut url = self.base.join("shadowBanUser")?;
+
+        {
+            let mut query = url.query_pairs_mut();
+            query.append_pair("userID", &request.user_id)
+                .append_pair("adminUserID", admin_user_id)
+                .append_pair("enabled", &request.enabled.to_string())
+                .append_pair("unHideOldSubmissions", &request.unhide_old_submissions.to_string())
+                .append_pair("deArrowTypes", &serde_json::to_string(&request.dearrow_types)?);
+
+            if let Some(categories) = &request.categories {
+                query.append_pair("categories", &serde_json::to_string(categories)?);
+            }
+        }
+
+        Ok(url)
+    }
+
+    fn branding_url(&self, video: &str) -> anyhow::Result<Url> {
+        let mut url = self.base.join("branding")?;
+        url.query_pairs_mut()
+            .append_pair("returnUserID", "true")
+            .append_pair("fetchAll", "true")
+            .append_pair("videoID", video);
+        Ok(url)
+    }
+
+    fn invalidate_branding(&self, video: &str) -> anyhow::Result<()> {
+        self.cache.invalidate(Endpoint::Branding, self.branding_url(video)?.as_str());
+        Ok(())
+    }
+
+    /// Describes a JSON request to `path`, replacing the private user ID in `private_field` with [`REDACTED`].
+    fn preview(&self, path: &str, body: &impl Serialize, private_field: &str, private_user_id: &str) -> anyhow::Result<RequestPreview> {
+        let mut body = serde_json::to_value(body).context("Failed to serialize request to JSON")?;
+
+        if let Some(value) = body.get_mut(private_field) {
+            *value = serde_json::Value::from(REDACTED);
+        }
+
+        Ok(RequestPreview {
+            method: reqwest::Method::POST,
+            url: self.base.join(path)?,
+            body: Some(body),
+            public_user_id: public_user_id(private_user_id),
+        })
+    }
+
+    fn post(&self, path: &str, body: &impl Serialize) -> anyhow::Result<reqwest::blocking::Response> {
+        let url = self.base.join(path)?;
+
+        Ok(self.client.post(url)
+            .header("User-Agent", crate::USER_AGENT)
+            .json(body)
+            .send()?)
+    }
+}
@@ -0,0 +1,54 @@
+// dearrow-cli - program to view and vote for DeArrow submissions
+// Copyright (C) 2024  mschae23
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anyhow::{Context, bail};
+use reqwest::Url;
+use serde::de::DeserializeOwned;
+
+use crate::cache::{Cache, Endpoint};
+
+pub mod main;
+pub mod browser;
+pub mod invidious;
+pub mod oembed;
+
+/// Sends a GET request and deserializes the JSON response, failing on any status other than 200.
+///
+/// Successful responses are stored in `cache`, keyed by the URL.
+fn get_json<T: DeserializeOwned>(client: &reqwest::blocking::Client, cache: &Cache, endpoint: Endpoint, url: Url) -> anyhow::Result<T> {
+    if let Some(body) = cache.get(endpoint, url.as_str()) {
+        // An entry that cannot be deserialized anymore (like after an update) is simply replaced
+        if let Ok(value) = serde_json::from_str(&body) {
+            return Ok(value);
+        }
+    }
+
+    let response = client.get(url.clone()).header("User-Agent", crate::USER_AGENT).send().context("Failed to send request")?;
+
+    if response.status() != 200 {
+        bail!("Request to {} failed. Response: {}\n{}", url, response.status(), response.text()?);
+    }
+
+    let body = response.text().context("Failed to read response")?;
+    let value = serde_json::from_str(&body).context("Failed to deserialize response")?;
+    cache.put(endpoint, url.as_str(), &body);
+    Ok(value)
+}
+
+/// Parses an API base URI. The trailing slash is significant, since paths are joined onto it.
+fn parse_base(base: &str) -> anyhow::Result<Url> {
+    Url::parse(base).with_context(|| format!("Invalid API URI: {}", base))
+}
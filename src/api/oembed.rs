@@ -0,0 +1,78 @@
+// dearrow-cli - program to view and vote for DeArrow submissions
+// Copyright (C) 2024  mschae23
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//! YouTube's oEmbed endpoint, used to get the original title of a video.
+
+use anyhow::Context;
+use reqwest::Url;
+use serde::Deserialize;
+
+use crate::cache::{Cache, Endpoint};
+use crate::original_title::OriginalTitleProvider;
+use crate::video::VideoId;
+
+/// The default oEmbed endpoint.
+pub const DEFAULT_OEMBED_API: &str = "https://www.youtube-nocookie.com/oembed";
+
+#[derive(Deserialize)]
+struct OEmbedResponse {
+    title: Option<String>,
+}
+
+/// A client for an oEmbed endpoint compatible with YouTube's.
+#[derive(Clone, Debug)]
+pub struct OEmbedClient {
+    client: reqwest::blocking::Client,
+    endpoint: Url,
+    cache: Cache,
+}
+
+impl OEmbedClient {
+    /// Creates a new client. Unlike the other API clients, `endpoint` is the full URI of the oEmbed endpoint.
+    pub fn new(client: reqwest::blocking::Client, endpoint: &str) -> anyhow::Result<Self> {
+        Ok(OEmbedClient {
+            client,
+            endpoint: super::parse_base(endpoint)?,
+            cache: Cache::disabled(),
+        })
+    }
+
+    /// Caches all responses in `cache`.
+    pub fn with_cache(self, cache: Cache) -> Self {
+        OEmbedClient {
+            cache,
+            ..self
+        }
+    }
+
+    pub fn endpoint(&self) -> &Url {
+        &self.endpoint
+    }
+
+    /// Gets the current title of a video.
+    pub fn get_original_title(&self, video: &VideoId) -> anyhow::Result<String> {
+        let mut url = self.endpoint.clone();
+        url.query_pairs_mut().append_pair("url", &format!("https://youtu.be/{}", video));
+
+        let response: OEmbedResponse = super::get_json(&self.client, &self.cache, Endpoint::OEmbed, url).context("Failed to get oembed response")?;
+        response.title.context("oembed response contained no title")
+    }
+}
+
+impl OriginalTitleProvider for OEmbedClient {
+    fn original_title(&self, video: &VideoId) -> anyhow::Result<Option<String>> {
+        self.get_original_title(video).map(Some)
+    }
+}
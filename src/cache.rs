@@ -0,0 +1,277 @@
+// dearrow-cli - program to view and vote for DeArrow submissions
+// Copyright (C) 2024  mschae23
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! An on-disk cache for API responses.
+//!
+//! Every cached response is stored as a JSON file in a directory per [`Endpoint`], named after a hash of the request URL.
+//! Errors reading or writing the cache are ignored, since they only make requests slower.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use anyhow::Context;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+/// The kinds of cached responses, each with its own time to live.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Endpoint {
+    /// Original titles from YouTube, which rarely change.
+    OEmbed,
+    /// Original titles from an Invidious instance.
+    Invidious,
+    /// Branding data from the main API, which changes with every vote.
+    Branding,
+    /// Data from DeArrow Browser, which is updated from database dumps.
+    Browser,
+}
+
+impl Endpoint {
+    pub const ALL: [Endpoint; 4] = [Endpoint::OEmbed, Endpoint::Invidious, Endpoint::Branding, Endpoint::Browser];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Endpoint::OEmbed => "oembed",
+            Endpoint::Invidious => "invidious",
+            Endpoint::Branding => "branding",
+            Endpoint::Browser => "browser",
+        }
+    }
+
+    pub fn ttl(self) -> Duration {
+        match self {
+            Endpoint::OEmbed | Endpoint::Invidious => Duration::from_secs(7 * 24 * 60 * 60),
+            Endpoint::Branding => Duration::from_secs(5 * 60),
+            Endpoint::Browser => Duration::from_secs(60 * 60),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CacheMode {
+    /// Use cached responses that have not expired, and cache new ones.
+    Enabled,
+    /// Always send requests, but cache their responses (`--refresh`).
+    Refresh,
+    /// Neither read nor write the cache (`--no-cache`).
+    Disabled,
+}
+
+#[derive(Clone, Debug)]
+pub struct Cache {
+    dir: Option<PathBuf>,
+    mode: CacheMode,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    /// The request URL, to detect hash collisions.
+    key: String,
+    /// When the response was received, in milliseconds since the Unix epoch.
+    time: i64,
+    body: String,
+}
+
+/// The number and size of cached responses for an endpoint.
+#[derive(Copy, Clone, Debug)]
+pub struct EndpointStats {
+    pub endpoint: Endpoint,
+    pub entries: u64,
+    /// How many of the entries have expired. They are overwritten on the next request, or removed by [`Cache::clear`].
+    pub expired: u64,
+    pub bytes: u64,
+}
+
+impl Cache {
+    pub fn new(dir: PathBuf, mode: CacheMode) -> Self {
+        Cache {
+            dir: Some(dir),
+            mode,
+        }
+    }
+
+    /// A cache that never stores anything.
+    pub fn disabled() -> Self {
+        Cache {
+            dir: None,
+            mode: CacheMode::Disabled,
+        }
+    }
+
+    /// The default cache directory, `$XDG_CACHE_HOME/dearrow-cli`.
+    pub fn default_dir() -> Option<PathBuf> {
+        dirs::cache_dir().map(|dir| dir.join("dearrow-cli"))
+    }
+
+    pub fn dir(&self) -> Option<&Path> {
+        self.dir.as_deref()
+    }
+
+    pub fn mode(&self) -> CacheMode {
+        self.mode
+    }
+
+    /// Returns a copy of this cache using a different mode, like [`CacheMode::Refresh`] for data that must be current.
+    ///
+    /// A disabled cache (`--no-cache`) stays disabled.
+    pub fn with_mode(&self, mode: CacheMode) -> Self {
+        Cache {
+            dir: self.dir.clone(),
+            mode: if self.mode == CacheMode::Disabled { CacheMode::Disabled } else { mode },
+        }
+    }
+
+    /// Gets a cached response body, if it exists and has not expired.
+    pub fn get(&self, endpoint: Endpoint, key: &str) -> Option<String> {
+        if self.mode != CacheMode::Enabled {
+            return None;
+        }
+
+        let contents = std::fs::read_to_string(self.path(endpoint, key)?).ok()?;
+        let entry = serde_json::from_str::<CacheEntry>(&contents).ok()?;
+
+        (entry.key == key && !is_expired(endpoint, entry.time)).then_some(entry.body)
+    }
+
+    /// Stores a response body.
+    pub fn put(&self, endpoint: Endpoint, key: &str, body: &str) {
+        if self.mode == CacheMode::Disabled {
+            return;
+        }
+
+        let Some(path) = self.path(endpoint, key) else {
+            return;
+        };
+
+        let entry = CacheEntry {
+            key: key.to_owned(),
+            time: Utc::now().timestamp_millis(),
+            body: body.to_owned(),
+        };
+
+        if let (Some(parent), Ok(contents)) = (path.parent(), serde_json::to_string(&entry)) {
+            let _ = std::fs::create_dir_all(parent).and_then(|()| std::fs::write(&path, contents));
+        }
+    }
+
+    /// Removes a cached response, for example after a vote changed the data.
+    pub fn invalidate(&self, endpoint: Endpoint, key: &str) {
+        if let Some(path) = self.path(endpoint, key) {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+
+    /// Removes all cached responses.
+    pub fn clear(&self) -> anyhow::Result<()> {
+        let Some(dir) = &self.dir else {
+            return Ok(());
+        };
+
+        for endpoint in Endpoint::ALL {
+            let path = dir.join(endpoint.name());
+
+            if path.exists() {
+                std::fs::remove_dir_all(&path).with_context(|| format!("Failed to remove {}", path.display()))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn stats(&self) -> anyhow::Result<Vec<EndpointStats>> {
+        let mut stats = Vec::new();
+
+        for endpoint in Endpoint::ALL {
+            let mut endpoint_stats = EndpointStats { endpoint, entries: 0, expired: 0, bytes: 0, };
+
+            if let Some(path) = self.dir.as_ref().map(|dir| dir.join(endpoint.name())).filter(|path| path.exists()) {
+                for file in std::fs::read_dir(&path).with_context(|| format!("Failed to read {}", path.display()))? {
+                    let file = file.with_context(|| format!("Failed to read {}", path.display()))?;
+                    endpoint_stats.entries += 1;
+                    endpoint_stats.bytes += file.metadata().map_or(0, |metadata| metadata.len());
+
+                    let entry = std::fs::read_to_string(file.path()).ok()
+                        .and_then(|contents| serde_json::from_str::<CacheEntry>(&contents).ok());
+
+                    // Unreadable entries count as expired, since they are never used
+                    let expired = match entry {
+                        Some(entry) => is_expired(endpoint, entry.time),
+                        None => true,
+                    };
+
+                    if expired {
+                        endpoint_stats.expired += 1;
+                    }
+                }
+            }
+
+            stats.push(endpoint_stats);
+        }
+
+        Ok(stats)
+    }
+
+    fn path(&self, endpoint: Endpoint, key: &str) -> Option<PathBuf> {
+        self.dir.as_ref().map(|dir| dir.join(endpoint.name()).join(format!("{}.json", stable_hash(key.as_bytes()))))
+    }
+}
+
+/// A 64-bit FNV-1a hash of `data` as 16 hex digits.
+///
+/// Unlike the standard library's hasher, this is stable across program versions, so it can be used for names and
+/// values that are stored on disk, like cache entries or the request hashes in the `batch` journal.
+pub fn stable_hash(data: &[u8]) -> String {
+    let hash = data.iter().fold(0xcbf29ce484222325u64, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x100000001b3));
+    format!("{:016x}", hash)
+}
+
+fn is_expired(endpoint: Endpoint, time: i64) -> bool {
+    Utc::now().timestamp_millis() - time > endpoint.ttl().as_millis() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("dearrow-cli-test-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn disabled_cache_stays_disabled() {
+        let dir = cache_dir("disabled");
+        let cache = Cache::new(dir.clone(), CacheMode::Disabled).with_mode(CacheMode::Refresh);
+
+        assert_eq!(cache.mode(), CacheMode::Disabled);
+        cache.put(Endpoint::Branding, "key", "body");
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn refresh_writes_but_does_not_read() {
+        let dir = cache_dir("refresh");
+        let cache = Cache::new(dir.clone(), CacheMode::Enabled);
+        let refresh = cache.with_mode(CacheMode::Refresh);
+
+        assert_eq!(refresh.mode(), CacheMode::Refresh);
+        refresh.put(Endpoint::Branding, "key", "body");
+        assert_eq!(refresh.get(Endpoint::Branding, "key"), None);
+        assert_eq!(cache.get(Endpoint::Branding, "key").as_deref(), Some("body"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,80 @@
+// dearrow-cli - program to view and vote for DeArrow submissions
+// Copyright (C) 2024  mschae23
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anyhow::Context;
+use dearrow_cli::api::main::{DeArrowType, ShadowBanRequest};
+use dearrow_cli::cache::CacheMode;
+
+use crate::{identity, BanArgs, Options, utils};
+
+pub fn run(options: Options, client: reqwest::blocking::Client, args: BanArgs, ban: bool) -> anyhow::Result<()> {
+    let private_user_id = identity::private_user_id(&options)?;
+    let browser_api = options.browser_api_client(&client)?.with_cache(options.cache.with_mode(CacheMode::Refresh));
+
+    let titles = browser_api.titles_by_user(&args.user)?;
+    let thumbnails = browser_api.thumbnails_by_user(&args.user)?;
+
+    // Banning hides submissions that aren't hidden yet, unbanning unhides the shadowhidden ones
+    let affected_titles = titles.iter().filter(|title| title.shadow_hidden != ban).count();
+    let affected_thumbnails = thumbnails.iter().filter(|thumbnail| thumbnail.shadow_hidden != ban).count();
+    let action = if ban { "hidden" } else { "unhidden" };
+
+    eprintln!("User {} has {} titles and {} thumbnails in DeArrow Browser.", args.user, titles.len(), thumbnails.len());
+    eprintln!("Titles that would be {}: {}", action, if args.keep_titles { 0 } else { affected_titles });
+    eprintln!("Thumbnails that would be {}: {}", action, if args.keep_thumbnails { 0 } else { affected_thumbnails });
+
+    if !args.keep_segments {
+        eprintln!("Existing SponsorBlock segments will be {} as well.", action);
+    }
+
+    let mut dearrow_types = Vec::new();
+
+    if !args.keep_titles {
+        dearrow_types.push(DeArrowType::Title);
+    }
+
+    if !args.keep_thumbnails {
+        dearrow_types.push(DeArrowType::Thumbnail);
+    }
+
+    let request = ShadowBanRequest {
+        admin_user_id: private_user_id,
+        user_id: args.user,
+        enabled: ban,
+        unhide_old_submissions: !(args.keep_titles && args.keep_thumbnails && args.keep_segments),
+        dearrow_types,
+        categories: if args.keep_segments { Some(Vec::new()) } else { None },
+    };
+
+    let prompt = if ban { format!("Shadowban user {}?", request.user_id) } else { format!("Unban user {}?", request.user_id) };
+
+    let main_api = options.main_api_client(&client)?;
+
+    if options.dry_run {
+        eprintln!("Not sending request (dry run): {}", main_api.preview_shadow_ban(&request)?);
+        return Ok(());
+    }
+
+    if !args.yes {
+        utils::require_confirmation(&prompt)?;
+    }
+
+    let response = main_api.post_shadow_ban(&request)?;
+    eprintln!("Sent request. Response: {}", response.status());
+
+    response.error_for_status().context("Server returned error")?;
+    Ok(())
+}
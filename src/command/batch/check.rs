@@ -0,0 +1,124 @@
+// dearrow-cli - program to view and vote for DeArrow submissions
+// Copyright (C) 2024  mschae23
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Pre-flight validation of a `batch` input file (`--check`), without sending any votes.
+
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::path::Path;
+use anyhow::bail;
+use dearrow_cli::original_title::OriginalTitle;
+use dearrow_cli::VideoId;
+
+use crate::{InputFormat, Options};
+use super::input::{self, Row, RowDefaults, RowSubmission};
+
+/// A problem with a row of the input file.
+struct Problem {
+    line: u64,
+    message: String,
+}
+
+/// Checks every row of the input file and prints all problems found.
+///
+/// Besides the checks done before every batch run, this detects duplicate rows for the same video and submission kind,
+/// titles identical to the video's original title, and title rows for videos that already have a locked title.
+/// Fails if there are any problems.
+pub fn run(options: &Options, client: &reqwest::blocking::Client, path: &Path, format: InputFormat, defaults: RowDefaults) -> anyhow::Result<()> {
+    let mut problems = Vec::new();
+    let mut rows = Vec::new();
+
+    for (line, row) in input::read_raw(path, format)? {
+        match row.and_then(|row| row.validate(line, defaults)) {
+            Ok(row) => rows.push(row),
+            Err(err) => problems.push(Problem { line, message: format!("{:#}", err), }),
+        }
+    }
+
+    let mut first_rows = HashMap::new();
+
+    for row in &rows {
+        match first_rows.entry((row.video.clone(), row.submission.kind())) {
+            Entry::Occupied(first) => problems.push(Problem { line: row.line, message: format!("Duplicate {} row for video {} (first on line {})", row.submission.kind(), row.video, first.get()), }),
+            Entry::Vacant(entry) => {
+                entry.insert(row.line);
+            },
+        }
+    }
+
+    check_titles(options, client, &rows, &mut problems)?;
+
+    problems.sort_by_key(|problem| problem.line);
+
+    for problem in &problems {
+        println!("Line {}: {}", problem.line, problem.message);
+    }
+
+    if !problems.is_empty() {
+        bail!("Found {} problems in {}", problems.len(), path.display());
+    }
+
+    eprintln!("No problems found in {} rows.", rows.len());
+    Ok(())
+}
+
+/// Compares title rows with the original title and the locked title of their video.
+fn check_titles(options: &Options, client: &reqwest::blocking::Client, rows: &[Row], problems: &mut Vec<Problem>) -> anyhow::Result<()> {
+    let main_api = options.main_api_client(client)?;
+    let original_titles = options.original_titles(client)?;
+    let mut videos: HashMap<VideoId, Vec<(u64, &str)>> = HashMap::new();
+
+    for row in rows {
+        if let RowSubmission::Title(title) = &row.submission {
+            if !row.downvote {
+                videos.entry(row.video.clone()).or_default().push((row.line, title));
+            }
+        }
+    }
+
+    for (video, titles) in videos {
+        // Failing to look up a video is not a problem with the file itself, so these are only warnings
+        let branding = main_api.get_branding(&video);
+        let original_title = original_titles.get(&video, || branding.as_ref().ok()
+            .and_then(|branding| branding.titles.iter().find(|title| title.original))
+            .map(|title| title.title.clone()));
+
+        if let OriginalTitle::Unavailable(reason) = &original_title {
+            eprintln!("Warning: failed to get original title of video {}: {}", video, reason);
+        }
+
+        if let Some(original_title) = original_title.title() {
+            for (line, title) in &titles {
+                if title.trim() == original_title.trim() {
+                    problems.push(Problem { line: *line, message: format!("Title is identical to the original title of video {}", video), });
+                }
+            }
+        }
+
+        match branding {
+            Ok(branding) => if let Some(locked) = branding.titles.iter().find(|title| title.locked) {
+                for (line, title) in &titles {
+                    if *title != locked.title {
+                        problems.push(Problem { line: *line, message: format!("Video {} already has a locked title: \"{}\"", video, locked.title), });
+                    }
+                }
+            },
+            Err(err) => eprintln!("Warning: failed to get branding data of video {}: {:#}", video, err),
+        }
+    }
+
+    Ok(())
+}
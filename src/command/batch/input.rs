@@ -0,0 +1,381 @@
+// dearrow-cli - program to view and vote for DeArrow submissions
+// Copyright (C) 2024  mschae23
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! The input file format of `batch`.
+//!
+//! Every row has the following named fields (CSV columns or JSON object keys). Empty or missing optional
+//! fields use the default from the command line.
+//!
+//! | Field        | Description                                                                   |
+//! |--------------|-------------------------------------------------------------------------------|
+//! | `video`      | Video ID or URL (required)                                                    |
+//! | `kind`       | `title`, `thumbnail` or `casual` (required)                                   |
+//! | `title`      | The title to vote for (required for `title`)                                  |
+//! | `timestamp`  | Thumbnail timestamp in seconds, or `original` (required for `thumbnail`)      |
+//! | `categories` | Casual categories, separated by commas or spaces; a list in JSON (`casual`)   |
+//! | `downvote`   | `true` to downvote instead of upvoting                                        |
+//! | `was_warned` | `true` to report a title as having been auto-warned                           |
+//! | `autolock`   | `false` to disable auto-lock (only has an effect for VIP users)               |
+
+use std::io::BufRead;
+use std::path::Path;
+use anyhow::{Context, anyhow, bail};
+use serde::Deserialize;
+use dearrow_cli::api::main::{BrandingVote, CasualCategory, CasualVote};
+use dearrow_cli::VideoId;
+
+use crate::InputFormat;
+
+/// A row of the input file as written by the user, before validation.
+#[derive(Deserialize, Clone, Debug)]
+pub struct RawRow {
+    pub video: String,
+    pub kind: RowKind,
+    pub title: Option<String>,
+    pub timestamp: Option<TimestampField>,
+    pub categories: Option<CategoriesField>,
+    pub downvote: Option<bool>,
+    pub was_warned: Option<bool>,
+    pub autolock: Option<bool>,
+}
+
+#[derive(Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RowKind {
+    Title,
+    Thumbnail,
+    Casual,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum TimestampField {
+    Seconds(f64),
+    /// Only `original` is valid here.
+    Text(String),
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum CategoriesField {
+    List(Vec<CasualCategory>),
+    Text(String),
+}
+
+/// The defaults for fields left empty in the input file, set by command line flags.
+#[derive(Copy, Clone, Debug)]
+pub struct RowDefaults {
+    pub downvote: bool,
+    pub was_warned: bool,
+    pub autolock: bool,
+}
+
+/// A validated row of the input file.
+#[derive(Clone, Debug)]
+pub struct Row {
+    /// The line number in the input file, starting at 1.
+    pub line: u64,
+    pub video: VideoId,
+    pub submission: RowSubmission,
+    pub downvote: bool,
+    pub was_warned: bool,
+    pub autolock: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RowSubmission {
+    Title(String),
+    /// `None` stands for the original thumbnail.
+    Thumbnail(Option<f64>),
+    Casual(Vec<CasualCategory>),
+}
+
+/// The request to send for a row.
+#[derive(Clone, Debug)]
+pub enum RowRequest {
+    Branding(BrandingVote),
+    Casual(CasualVote),
+}
+
+impl InputFormat {
+    /// Files ending in `.jsonl` or `.ndjson` are JSON lines, everything else is CSV.
+    pub fn of_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("jsonl" | "ndjson") => InputFormat::Jsonl,
+            _ => InputFormat::Csv,
+        }
+    }
+}
+
+/// Reads all rows of the input file, together with their line numbers.
+///
+/// Only errors reading the file itself fail the whole function; rows that cannot be parsed are returned as errors.
+pub fn read_raw(path: &Path, format: InputFormat) -> anyhow::Result<Vec<(u64, anyhow::Result<RawRow>)>> {
+    let file = std::fs::File::open(path).context("Failed to open input file")?;
+    let reader = std::io::BufReader::new(file);
+
+    match format {
+        InputFormat::Csv => {
+            let mut reader = csv::Reader::from_reader(reader);
+            let headers = reader.headers().context("Failed to read header of input file")?.clone();
+            let mut rows = Vec::new();
+
+            for record in reader.records() {
+                let record = record.context("Failed to read line in input file")?;
+                let line = record.position().map_or(0, |position| position.line());
+                rows.push((line, record.deserialize(Some(&headers)).map_err(anyhow::Error::from)));
+            }
+
+            Ok(rows)
+        },
+        InputFormat::Jsonl => {
+            let mut rows = Vec::new();
+
+            for (i, line) in reader.lines().enumerate() {
+                let line = line.context("Failed to read line in input file")?;
+
+                if line.trim().is_empty() {
+                    continue;
+                }
+
+                rows.push((i as u64 + 1, serde_json::from_str(&line).map_err(anyhow::Error::from)));
+            }
+
+            Ok(rows)
+        },
+    }
+}
+
+/// Reads and validates all rows of the input file. Fails on the first invalid row.
+pub fn read(path: &Path, format: InputFormat, defaults: RowDefaults) -> anyhow::Result<Vec<Row>> {
+    read_raw(path, format)?.into_iter()
+        .map(|(line, row)| row.and_then(|row| row.validate(line, defaults)).with_context(|| format!("Invalid row on line {}", line)))
+        .collect()
+}
+
+impl RawRow {
+    pub fn validate(self, line: u64, defaults: RowDefaults) -> anyhow::Result<Row> {
+        let video = self.video.parse::<VideoId>()?;
+
+        let submission = match self.kind {
+            RowKind::Title => {
+                let title = self.title.ok_or_else(|| anyhow!("Missing title"))?;
+
+                if title.trim().is_empty() {
+                    bail!("Title is empty");
+                }
+
+                RowSubmission::Title(title)
+            },
+            RowKind::Thumbnail => RowSubmission::Thumbnail(match self.timestamp.ok_or_else(|| anyhow!("Missing timestamp"))? {
+                TimestampField::Seconds(timestamp) if timestamp.is_finite() && timestamp >= 0.0 => Some(timestamp),
+                TimestampField::Seconds(timestamp) => bail!("Invalid timestamp: {}", timestamp),
+                TimestampField::Text(text) if text.trim().eq_ignore_ascii_case("original") => None,
+                TimestampField::Text(text) => bail!("Invalid timestamp: {} (expected a number of seconds or \"original\")", text),
+            }),
+            RowKind::Casual => RowSubmission::Casual(match self.categories {
+                None => Vec::new(),
+                Some(CategoriesField::List(categories)) => categories,
+                Some(CategoriesField::Text(text)) => text.split(|c: char| c == ',' || c.is_whitespace())
+                    .filter(|category| !category.is_empty())
+                    .map(|category| <CasualCategory as clap::ValueEnum>::from_str(category, true)
+                        .map_err(|_| anyhow!("Unknown casual category: {}", category)))
+                    .collect::<anyhow::Result<Vec<_>>>()?,
+            }),
+        };
+
+        let downvote = self.downvote.unwrap_or(defaults.downvote);
+
+        if let RowSubmission::Casual(categories) = &submission {
+            if categories.is_empty() && !downvote {
+                bail!("Missing casual categories");
+            }
+        }
+
+        Ok(Row {
+            line,
+            video,
+            submission,
+            downvote,
+            was_warned: self.was_warned.unwrap_or(defaults.was_warned),
+            autolock: self.autolock.unwrap_or(defaults.autolock),
+        })
+    }
+}
+
+impl Row {
+    /// Creates the request for this row.
+    pub fn request(&self, private_user_id: String, using_casual: bool) -> RowRequest {
+        let video = self.video.to_string();
+
+        let branding = |request: BrandingVote| RowRequest::Branding(BrandingVote {
+            downvote: self.downvote,
+            auto_lock: self.autolock,
+            casual_mode: using_casual,
+            ..request
+        });
+
+        match &self.submission {
+            RowSubmission::Title(title) => branding(BrandingVote {
+                was_warned: self.was_warned,
+                ..BrandingVote::title(private_user_id, video, title.clone())
+            }),
+            RowSubmission::Thumbnail(timestamp) => branding(BrandingVote::thumbnail(private_user_id, video, *timestamp)),
+            RowSubmission::Casual(_) if self.downvote => RowRequest::Casual(CasualVote::downvote(private_user_id, video)),
+            RowSubmission::Casual(categories) => RowRequest::Casual(CasualVote::new(private_user_id, video, categories.clone())),
+        }
+    }
+}
+
+impl RowSubmission {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            RowSubmission::Title(_) => "title",
+            RowSubmission::Thumbnail(_) => "thumbnail",
+            RowSubmission::Casual(_) => "casual",
+        }
+    }
+
+    /// The value of the `title`, `timestamp` or `categories` column.
+    pub fn value(&self) -> String {
+        match self {
+            RowSubmission::Title(title) => title.clone(),
+            RowSubmission::Thumbnail(Some(timestamp)) => timestamp.to_string(),
+            RowSubmission::Thumbnail(None) => String::from("original"),
+            RowSubmission::Casual(categories) => categories.iter().map(|category| category.name()).collect::<Vec<_>>().join(","),
+        }
+    }
+}
+
+impl std::fmt::Display for RowSubmission {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RowSubmission::Title(title) => write!(f, "title \"{}\"", title),
+            RowSubmission::Thumbnail(Some(timestamp)) => write!(f, "thumbnail at {}", timestamp),
+            RowSubmission::Thumbnail(None) => write!(f, "original thumbnail"),
+            RowSubmission::Casual(categories) => write!(f, "casual {}", categories.iter()
+                .map(|category| category.name()).collect::<Vec<_>>().join(", ")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use super::*;
+
+    const DEFAULTS: RowDefaults = RowDefaults { downvote: false, was_warned: false, autolock: true };
+
+    /// Writes `contents` to a file in the temporary directory that is unique to this test.
+    fn input_file(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("dearrow-cli-test-{}-{}", std::process::id(), name));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn read_error(name: &str, format: InputFormat, contents: &str) -> String {
+        let path = input_file(name, contents);
+        let result = read(&path, format, DEFAULTS);
+        std::fs::remove_file(&path).unwrap();
+        format!("{:#}", result.unwrap_err())
+    }
+
+    #[test]
+    fn reads_valid_rows() {
+        let path = input_file("valid.csv", "video,kind,title,timestamp,categories,downvote,autolock\n\
+            dQw4w9WgXcQ,title,Some title,,,,\n\
+            https://youtu.be/dQw4w9WgXcQ,thumbnail,,original,,,false\n\
+            dQw4w9WgXcQ,thumbnail,,12.5,,true,\n\
+            dQw4w9WgXcQ,casual,,,\"funny, clever\",,\n");
+        let rows = read(&path, InputFormat::Csv, DEFAULTS).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let submissions = rows.iter().map(|row| row.submission.clone()).collect::<Vec<_>>();
+        assert_eq!(submissions, vec![
+            RowSubmission::Title(String::from("Some title")),
+            RowSubmission::Thumbnail(None),
+            RowSubmission::Thumbnail(Some(12.5)),
+            RowSubmission::Casual(vec![CasualCategory::Funny, CasualCategory::Clever]),
+        ]);
+        assert_eq!(rows.iter().map(|row| row.line).collect::<Vec<_>>(), vec![2, 3, 4, 5]);
+        assert_eq!(rows.iter().map(|row| (row.downvote, row.autolock)).collect::<Vec<_>>(),
+            vec![(false, true), (false, false), (true, true), (false, true)]);
+    }
+
+    #[test]
+    fn reads_valid_jsonl_rows() {
+        let path = input_file("valid.jsonl", "{\"video\": \"dQw4w9WgXcQ\", \"kind\": \"thumbnail\", \"timestamp\": 3}\n\
+            \n\
+            {\"video\": \"dQw4w9WgXcQ\", \"kind\": \"casual\", \"categories\": [\"descriptive\"], \"downvote\": false}\n");
+        let rows = read(&path, InputFormat::Jsonl, DEFAULTS).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(rows.iter().map(|row| (row.line, row.submission.clone())).collect::<Vec<_>>(), vec![
+            (1, RowSubmission::Thumbnail(Some(3.0))),
+            (3, RowSubmission::Casual(vec![CasualCategory::Descriptive])),
+        ]);
+    }
+
+    #[test]
+    fn rejects_invalid_csv_rows() {
+        for (name, row, error) in [
+            ("video.csv", "not a video,title,Some title,,", "Invalid video ID"),
+            ("missing-title.csv", "dQw4w9WgXcQ,title,,,", "Missing title"),
+            ("empty-title.csv", "dQw4w9WgXcQ,title,\"  \",,", "Title is empty"),
+            ("missing-timestamp.csv", "dQw4w9WgXcQ,thumbnail,,,", "Missing timestamp"),
+            ("negative-timestamp.csv", "dQw4w9WgXcQ,thumbnail,,-1,", "Invalid timestamp: -1"),
+            ("text-timestamp.csv", "dQw4w9WgXcQ,thumbnail,,start,", "Invalid timestamp: start"),
+            ("category.csv", "dQw4w9WgXcQ,casual,,,\"funny,boring\"", "Unknown casual category: boring"),
+            ("no-categories.csv", "dQw4w9WgXcQ,casual,,,", "Missing casual categories"),
+        ] {
+            let error_message = read_error(name, InputFormat::Csv, &format!("video,kind,title,timestamp,categories\ndQw4w9WgXcQ,title,Fine,,\n{}\n", row));
+            assert!(error_message.starts_with("Invalid row on line 3: "), "{}: {}", name, error_message);
+            assert!(error_message.contains(error), "{}: {}", name, error_message);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_jsonl_rows() {
+        for (name, row, error) in [
+            ("kind.jsonl", "{\"video\": \"dQw4w9WgXcQ\", \"kind\": \"segment\"}", "unknown variant"),
+            ("missing-video.jsonl", "{\"kind\": \"title\", \"title\": \"Some title\"}", "missing field `video`"),
+            ("syntax.jsonl", "{\"video\": \"dQw4w9WgXcQ\",", "EOF"),
+            ("category.jsonl", "{\"video\": \"dQw4w9WgXcQ\", \"kind\": \"casual\", \"categories\": [\"boring\"]}", "did not match any variant"),
+        ] {
+            let error_message = read_error(name, InputFormat::Jsonl, &format!("\n{}\n", row));
+            assert!(error_message.starts_with("Invalid row on line 2: "), "{}: {}", name, error_message);
+            assert!(error_message.contains(error), "{}: {}", name, error_message);
+        }
+    }
+
+    #[test]
+    fn casual_downvote_needs_no_categories() {
+        let path = input_file("casual-downvote.csv", "video,kind,downvote\ndQw4w9WgXcQ,casual,true\n");
+        let rows = read(&path, InputFormat::Csv, DEFAULTS).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(rows[0].submission, RowSubmission::Casual(Vec::new()));
+        assert!(rows[0].downvote);
+    }
+
+    #[test]
+    fn detects_format_from_extension() {
+        assert_eq!(InputFormat::of_path(Path::new("rows.jsonl")), InputFormat::Jsonl);
+        assert_eq!(InputFormat::of_path(Path::new("rows.ndjson")), InputFormat::Jsonl);
+        assert_eq!(InputFormat::of_path(Path::new("rows.csv")), InputFormat::Csv);
+        assert_eq!(InputFormat::of_path(Path::new("rows")), InputFormat::Csv);
+    }
+}
@@ -0,0 +1,242 @@
+// dearrow-cli - program to view and vote for DeArrow submissions
+// Copyright (C) 2024  mschae23
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! The progress journal of `batch`, which allows resuming a run that was interrupted.
+//!
+//! The journal is a JSON lines file with one entry per processed row, appended (and flushed)
+//! right after the row's request has been sent.
+
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use anyhow::{Context, bail};
+use serde::{Deserialize, Serialize};
+use dearrow_cli::cache::stable_hash;
+
+use super::input::RowRequest;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct JournalEntry {
+    /// The line number of the row in the input file.
+    pub line: u64,
+    pub video: String,
+    /// See [`request_hash`].
+    pub hash: String,
+    /// `None` if no response was received.
+    pub status: Option<u16>,
+    /// When the request was sent, in milliseconds since the Unix epoch.
+    pub time: i64,
+}
+
+impl JournalEntry {
+    pub fn is_success(&self) -> bool {
+        self.status.is_some_and(|status| (200..300).contains(&status))
+    }
+}
+
+pub struct Journal {
+    file: File,
+    entries: Vec<JournalEntry>,
+}
+
+/// The default journal location, `<FILE>.journal` next to the input file.
+pub fn default_path(input: &Path) -> PathBuf {
+    let mut path = input.as_os_str().to_owned();
+    path.push(".journal");
+    PathBuf::from(path)
+}
+
+impl Journal {
+    /// Opens the journal at `path`. When resuming, the existing entries are read; otherwise,
+    /// an existing non-empty journal is an error, so that a finished or interrupted run is not repeated by accident.
+    pub fn open(path: &Path, resume: bool) -> anyhow::Result<Self> {
+        let exists = path.metadata().is_ok_and(|metadata| metadata.len() > 0);
+
+        if exists && !resume {
+            bail!("Journal file {} already exists. Pass --resume to continue the previous run, or delete it to start over", path.display());
+        }
+
+        let entries = if exists { read(path)? } else { Vec::new() };
+
+        let file = OpenOptions::new().create(true).append(true).open(path)
+            .with_context(|| format!("Failed to open journal file {}", path.display()))?;
+
+        Ok(Journal {
+            file,
+            entries,
+        })
+    }
+
+    pub fn entries(&self) -> &[JournalEntry] {
+        &self.entries
+    }
+
+    /// Whether the request for the row on `line` was already sent successfully.
+    ///
+    /// If `hash` is given, the request must also be the same as the one in the journal.
+    pub fn is_done(&self, line: u64, hash: Option<&str>) -> bool {
+        self.entries.iter().any(|entry| entry.line == line && entry.is_success() && match hash {
+            Some(hash) => entry.hash == hash,
+            None => true,
+        })
+    }
+
+    /// Whether a request was sent successfully for the row on `line`, but it was different from the current one.
+    pub fn has_changed(&self, line: u64, hash: &str) -> bool {
+        self.entries.iter().any(|entry| entry.line == line && entry.is_success() && entry.hash != hash)
+    }
+
+    pub fn record(&mut self, entry: JournalEntry) -> anyhow::Result<()> {
+        serde_json::to_writer(&mut self.file, &entry).context("Failed to write journal entry")?;
+        writeln!(self.file).context("Failed to write journal entry")?;
+        self.file.sync_data().context("Failed to write journal entry")?;
+
+        self.entries.push(entry);
+        Ok(())
+    }
+}
+
+fn read(path: &Path) -> anyhow::Result<Vec<JournalEntry>> {
+    let file = File::open(path).with_context(|| format!("Failed to open journal file {}", path.display()))?;
+    let mut entries = Vec::new();
+
+    for (i, line) in std::io::BufReader::new(file).lines().enumerate() {
+        let line = line.context("Failed to read journal file")?;
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        // The last line may be cut off if the program was killed while writing it
+        match serde_json::from_str(&line) {
+            Ok(entry) => entries.push(entry),
+            Err(err) => eprintln!("Ignoring invalid journal entry on line {}: {}", i + 1, err),
+        }
+    }
+
+    Ok(entries)
+}
+
+/// A [stable hash](stable_hash) of the request body without the private user ID and user agent,
+/// to detect rows that changed between runs.
+pub fn request_hash(request: &RowRequest) -> anyhow::Result<String> {
+    let json = match request.clone() {
+        RowRequest::Branding(mut request) => {
+            request.user_id.clear();
+            request.user_agent.clear();
+            serde_json::to_vec(&request)
+        },
+        RowRequest::Casual(mut request) => {
+            request.user_id.clear();
+            request.user_agent.clear();
+            serde_json::to_vec(&request)
+        },
+    }.context("Failed to serialize request to JSON")?;
+
+    Ok(stable_hash(&json))
+}
+
+#[cfg(test)]
+mod tests {
+    use dearrow_cli::api::main::BrandingVote;
+    use super::*;
+
+    fn journal_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("dearrow-cli-test-{}-{}.journal", std::process::id(), name));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    fn entry(line: u64, hash: &str, status: Option<u16>) -> JournalEntry {
+        JournalEntry {
+            line,
+            video: String::from("dQw4w9WgXcQ"),
+            hash: String::from(hash),
+            status,
+            time: 0,
+        }
+    }
+
+    fn title_request(user_id: &str, title: &str) -> RowRequest {
+        RowRequest::Branding(BrandingVote::title(String::from(user_id), String::from("dQw4w9WgXcQ"), String::from(title)))
+    }
+
+    #[test]
+    fn detects_done_and_changed_rows() {
+        let path = journal_path("done");
+        let mut journal = Journal::open(&path, false).unwrap();
+        journal.record(entry(2, "aaaa", Some(200))).unwrap();
+        journal.record(entry(3, "bbbb", Some(429))).unwrap();
+        journal.record(entry(4, "cccc", None)).unwrap();
+        drop(journal);
+
+        let journal = Journal::open(&path, true).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(journal.entries().len(), 3);
+
+        assert!(journal.is_done(2, None));
+        assert!(journal.is_done(2, Some("aaaa")));
+        assert!(!journal.is_done(2, Some("dddd")));
+        assert!(!journal.has_changed(2, "aaaa"));
+        assert!(journal.has_changed(2, "dddd"));
+
+        // Failed requests are sent again, whether or not the row changed
+        for line in [3, 4] {
+            assert!(!journal.is_done(line, None));
+            assert!(!journal.has_changed(line, "dddd"));
+        }
+
+        assert!(!journal.is_done(5, None));
+        assert!(!journal.has_changed(5, "aaaa"));
+    }
+
+    #[test]
+    fn refuses_existing_journal_without_resume() {
+        let path = journal_path("existing");
+        Journal::open(&path, false).unwrap().record(entry(2, "aaaa", Some(200))).unwrap();
+
+        let result = Journal::open(&path, false);
+        std::fs::remove_file(&path).unwrap();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn ignores_cut_off_last_entry() {
+        let path = journal_path("cut-off");
+        let mut journal = Journal::open(&path, false).unwrap();
+        journal.record(entry(2, "aaaa", Some(200))).unwrap();
+        drop(journal);
+
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        write!(file, "{{\"line\":3,\"video\":\"dQw4w").unwrap();
+        drop(file);
+
+        let journal = Journal::open(&path, true).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(journal.entries().len(), 1);
+        assert!(journal.is_done(2, Some("aaaa")));
+        assert!(!journal.is_done(3, None));
+    }
+
+    #[test]
+    fn request_hash_ignores_private_user_id() {
+        let hash = request_hash(&title_request("private1", "Some title")).unwrap();
+
+        assert_eq!(hash.len(), 16);
+        assert_eq!(hash, request_hash(&title_request("private2", "Some title")).unwrap());
+        assert_ne!(hash, request_hash(&title_request("private1", "Another title")).unwrap());
+    }
+}
@@ -0,0 +1,477 @@
+// dearrow-cli - program to view and vote for DeArrow submissions
+// Copyright (C) 2024  mschae23
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs::File;
+use std::time::Duration;
+use anyhow::{Context, anyhow, bail};
+use chrono::{SecondsFormat, Utc};
+use dearrow_cli::api::main::{BrandingVote, MainApiClient, MainApiResponse};
+use dearrow_cli::cache::CacheMode;
+use dearrow_cli::flags::Flags;
+use dearrow_cli::original_title::{OriginalTitle, OriginalTitleLookup};
+use dearrow_cli::VideoId;
+
+use crate::{identity, BatchArgs, OnLocked, Options, utils};
+
+pub mod check;
+pub mod input;
+pub mod journal;
+pub mod pacing;
+pub mod report;
+
+use input::{RowDefaults, RowRequest, RowSubmission};
+use journal::{Journal, JournalEntry};
+use pacing::Pacer;
+use report::{Report, ReportRecord, RowOutcome, RowResult};
+
+/// How often a row is retried after the server responded with 429 Too Many Requests.
+const MAX_RATE_LIMIT_RETRIES: u32 = 5;
+
+pub fn run(options: Options, client: reqwest::blocking::Client, _terminal_width: u16, args: BatchArgs) -> anyhow::Result<()> {
+    if args.interactive {
+        return run_interactive(options, client, args);
+    }
+
+    let format = args.input_format.unwrap_or_else(|| crate::InputFormat::of_path(&args.input));
+    let defaults = RowDefaults {
+        downvote: args.downvote,
+        was_warned: args.was_warned,
+        autolock: !args.no_autolock,
+    };
+
+    if args.check {
+        return check::run(&options, &client, &args.input, format, defaults);
+    }
+
+    // Read the whole file first, so that invalid rows are reported before sending any request
+    let rows = input::read(&args.input, format, defaults)?;
+
+    let private_user_id = identity::private_user_id(&options)?;
+    let mut submitter = Submitter::new(&options, client.clone(), &args)?;
+
+    for row in rows {
+        let request = row.request(private_user_id.clone(), args.using_casual);
+        let hash = journal::request_hash(&request)?;
+
+        let outcome = if submitter.is_done(row.line, Some(&hash)) {
+            RowOutcome::skipped()
+        } else {
+            if submitter.journal.as_ref().is_some_and(|journal| journal.has_changed(row.line, &hash)) {
+                eprintln!("Warning: the row on line {} changed since the previous run, sending it again.", row.line);
+            }
+
+            eprintln!("[line {}, {}] {}{}", row.line, row.video, if row.downvote { "downvote " } else { "" }, row.submission);
+
+            match submitter.check_locked(&row.video, &row.submission, row.downvote, row.autolock)? {
+                Some(outcome) => outcome,
+                None => submitter.send(row.line, &row.video, &request, hash)?,
+            }
+        };
+
+        let original_title = submitter.report_original_title(&row.video);
+        submitter.record(ReportRecord {
+            line: row.line,
+            video: row.video.to_string(),
+            kind: row.submission.kind(),
+            original_title,
+            submission: row.submission.value(),
+            downvote: row.downvote,
+            result: outcome.result,
+            status: outcome.status,
+            error: outcome.error,
+            time: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+        })?;
+    }
+
+    submitter.finish()
+}
+
+/// Prompts for a new title for every row of a CSV file whose first column is the video ID,
+/// showing the second column (like the current title) and the original title.
+fn run_interactive(options: Options, client: reqwest::blocking::Client, args: BatchArgs) -> anyhow::Result<()> {
+    let file = File::open(&args.input).context("Failed to open input file")?;
+    let reader = std::io::BufReader::new(file);
+
+    let stdin = std::io::stdin();
+    let mut buf = String::new();
+    let mut reader = csv::Reader::from_reader(reader);
+    let mut rows = Vec::new();
+
+    // Read the whole file first, so that invalid video IDs are reported before sending any request
+    for record in reader.records() {
+        let record = record.context("Failed to read line in input file")?;
+        let line = record.position().map_or(0, |position| position.line());
+        let video_id = record.get(0).ok_or(anyhow!("Failed to get column 0 from CSV record"))?;
+        let video_id = video_id.parse::<VideoId>().with_context(|| format!("Invalid video ID on line {}", line))?;
+        let old_title = record.get(1).ok_or(anyhow!("Failed to get column 1 from CSV record"))?.to_owned();
+        rows.push((line, video_id, old_title));
+    }
+
+    let private_user_id = identity::private_user_id(&options)?;
+    let mut submitter = Submitter::new(&options, client.clone(), &args)?;
+
+    for (line, video_id, old_title) in rows {
+        let mut record = ReportRecord {
+            line,
+            video: video_id.to_string(),
+            kind: "title",
+            original_title: None,
+            submission: String::new(),
+            downvote: args.downvote,
+            result: RowResult::Skipped,
+            status: None,
+            error: None,
+            time: String::new(),
+        };
+
+        // The new title is only known after prompting, so any earlier success counts
+        if submitter.is_done(line, None) {
+            record.original_title = submitter.report_original_title(&video_id);
+        } else if let Some(outcome) = submitter.check_locked(&video_id, &RowSubmission::Title(String::new()), args.downvote, !args.no_autolock)? {
+            // An empty title never equals the locked one, so any locked title is shown before prompting
+            record.original_title = submitter.report_original_title(&video_id);
+            record.result = outcome.result;
+            record.error = outcome.error;
+        } else {
+            let original_title = submitter.original_title(&video_id);
+
+            if let Some(note) = original_title.note() {
+                eprintln!("Original title {}", note);
+            }
+
+            eprintln!("[{}, {}] {}", video_id, original_title.title().unwrap_or("(unknown original title)"), old_title);
+            record.original_title = original_title.into_title();
+
+            if stdin.read_line(&mut buf).context("Failed to read stdin")? == 0 {
+                // End of input, the remaining rows stay unprocessed, so that they can be resumed
+                eprintln!("End of input, stopping.");
+                break;
+            }
+
+            let title = buf.trim_end_matches(['\r', '\n']);
+
+            if title.is_empty() {
+                eprintln!("Skipped.\n");
+            } else {
+                let title = title.to_string();
+                let request = RowRequest::Branding(BrandingVote {
+                    downvote: args.downvote,
+                    auto_lock: !args.no_autolock,
+                    was_warned: args.was_warned,
+                    casual_mode: args.using_casual,
+                    ..BrandingVote::title(private_user_id.clone(), video_id.to_string(), title.clone())
+                });
+
+                let hash = journal::request_hash(&request)?;
+                let outcome = submitter.send(line, &video_id, &request, hash)?;
+                record.submission = title;
+                record.result = outcome.result;
+                record.status = outcome.status;
+                record.error = outcome.error;
+            }
+
+            buf.clear();
+        }
+
+        record.time = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+        submitter.record(record)?;
+    }
+
+    submitter.finish()
+}
+
+#[derive(Default, Debug)]
+struct Summary {
+    sent: u64,
+    skipped: u64,
+    failed: u64,
+}
+
+/// Sends the requests of a batch run and keeps track of its progress.
+struct Submitter {
+    original_titles: OriginalTitleLookup,
+    main_api: MainApiClient,
+    /// The original titles submitted to DeArrow, from the branding data requested by [`Submitter::check_locked`].
+    dearrow_original_titles: RefCell<HashMap<VideoId, Option<String>>>,
+    /// `None` when simulating.
+    journal: Option<Journal>,
+    report: Option<Report>,
+    pacer: Pacer,
+    on_locked: OnLocked,
+    summary: Summary,
+}
+
+impl Submitter {
+    fn new(options: &Options, client: reqwest::blocking::Client, args: &BatchArgs) -> anyhow::Result<Self> {
+        let main_api = options.main_api_client(&client)?.with_cache(options.cache.with_mode(CacheMode::Refresh));
+
+        let journal = if args.simulate {
+            None
+        } else {
+            let path = args.journal.clone().unwrap_or_else(|| journal::default_path(&args.input));
+            let journal = Journal::open(&path, args.resume)?;
+
+            if !journal.entries().is_empty() {
+                eprintln!("Resuming from journal {} ({} entries).", path.display(), journal.entries().len());
+            }
+
+            Some(journal)
+        };
+
+        let report = args.report.as_deref().map(|path| Report::open(path, args.resume)).transpose()?;
+
+        // Requests from a previous run count towards --max-per-hour as well
+        let history = journal.iter().flat_map(|journal| journal.entries())
+            .filter(|entry| entry.status.is_some())
+            .map(|entry| entry.time);
+        let pacer = Pacer::new(args.min_interval.map(Duration::from_secs_f64), args.max_per_hour, history);
+
+        Ok(Submitter {
+            original_titles: options.original_titles(&client)?,
+            main_api,
+            dearrow_original_titles: RefCell::new(HashMap::new()),
+            journal,
+            report,
+            pacer,
+            on_locked: args.on_locked,
+            summary: Summary::default(),
+        })
+    }
+
+    fn is_done(&self, line: u64, hash: Option<&str>) -> bool {
+        self.journal.as_ref().is_some_and(|journal| journal.is_done(line, hash))
+    }
+
+    /// Gets the original title of a video for the report. Returns `None` without a request if there is no report.
+    fn report_original_title(&self, video: &VideoId) -> Option<String> {
+        self.report.as_ref()?;
+
+        let original_title = self.original_title(video);
+
+        if let OriginalTitle::Unavailable(reason) = &original_title {
+            eprintln!("Failed to get original title of {}: {}", video, reason);
+        }
+
+        original_title.into_title()
+    }
+
+    /// Gets the original title of a video, falling back to the original title submitted to DeArrow.
+    fn original_title(&self, video: &VideoId) -> OriginalTitle {
+        self.original_titles.get(video, || {
+            // Reuse the branding data requested to check for locked submissions, if there was one
+            if let Some(title) = self.dearrow_original_titles.borrow().get(video) {
+                return title.clone();
+            }
+
+            dearrow_original_title(&self.main_api.get_branding(video).ok()?)
+        })
+    }
+
+    /// Shows the locked title or thumbnail the row would replace, if any, and applies the `--on-locked` policy.
+    ///
+    /// Returns the outcome of the row if it should not be sent.
+    fn check_locked(&self, video: &VideoId, submission: &RowSubmission, downvote: bool, autolock: bool) -> anyhow::Result<Option<RowOutcome>> {
+        // Only upvotes with auto-lock enabled can replace a locked submission
+        if downvote || !autolock || matches!(submission, RowSubmission::Casual(_)) {
+            return Ok(None);
+        }
+
+        let branding = self.main_api.get_branding(video).inspect(|branding| {
+            self.dearrow_original_titles.borrow_mut().insert(video.clone(), dearrow_original_title(branding));
+        });
+
+        let locked = match branding.map(|branding| locked_submission(&branding, submission)) {
+            Ok(Some(locked)) => locked,
+            Ok(None) => return Ok(None),
+            Err(err) if self.on_locked == OnLocked::Override => {
+                eprintln!("Warning: failed to check for a locked submission: {:#}", err);
+                return Ok(None);
+            },
+            Err(err) => {
+                eprintln!("Failed to check for a locked submission: {:#}\n", err);
+
+                return Ok(Some(RowOutcome {
+                    result: RowResult::Failed,
+                    status: None,
+                    error: Some(format!("Failed to check for a locked submission: {:#}", err)),
+                }));
+            },
+        };
+
+        eprintln!("Video {} already has a locked {}.", video, locked);
+
+        let skip = match self.on_locked {
+            OnLocked::Skip => true,
+            OnLocked::Ask => !utils::confirm("Vote anyway?")?,
+            OnLocked::Override => false,
+        };
+
+        if !skip {
+            return Ok(None);
+        }
+
+        eprintln!("Skipped.\n");
+
+        Ok(Some(RowOutcome {
+            result: RowResult::Skipped,
+            status: None,
+            error: Some(format!("Video already has a locked {}", locked)),
+        }))
+    }
+
+    /// Sends the request for a row and records the result in the journal.
+    ///
+    /// If the server responds with 429 Too Many Requests, the request is retried after the time it asks for.
+    /// Failed requests are returned as such; only errors writing the journal are returned as errors.
+    fn send(&mut self, line: u64, video: &VideoId, request: &RowRequest, hash: String) -> anyhow::Result<RowOutcome> {
+        let Some(journal) = &mut self.journal else {
+            let preview = match request {
+                RowRequest::Branding(request) => self.main_api.preview_branding(request)?,
+                RowRequest::Casual(request) => self.main_api.preview_casual(request)?,
+            };
+
+            eprintln!("Not sending request: {}\n", preview);
+
+            return Ok(RowOutcome {
+                result: RowResult::Simulated,
+                status: None,
+                error: None,
+            });
+        };
+
+        let mut retries = 0;
+
+        loop {
+            self.pacer.wait();
+
+            let time = Utc::now().timestamp_millis();
+            let response = match request {
+                RowRequest::Branding(request) => self.main_api.post_branding(request),
+                RowRequest::Casual(request) => self.main_api.post_casual(request),
+            };
+
+            let (status, error, retry_after) = match response {
+                Ok(response) => {
+                    self.pacer.record(time);
+                    eprintln!("Sent request. Response: {}", response.status());
+
+                    let status = response.status();
+                    let retry_after = (status == reqwest::StatusCode::TOO_MANY_REQUESTS)
+                        .then(|| pacing::retry_after(response.headers()));
+
+                    let error = if status.is_success() {
+                        None
+                    } else {
+                        let body = response.text().unwrap_or_default();
+
+                        if !body.is_empty() {
+                            eprintln!("{}", body);
+                        }
+
+                        Some(body)
+                    };
+
+                    (Some(status.as_u16()), error, retry_after)
+                },
+                Err(err) => {
+                    eprintln!("Failed to send request: {:#}", err);
+                    (None, Some(format!("{:#}", err)), None)
+                },
+            };
+
+            let entry = JournalEntry {
+                line,
+                video: video.to_string(),
+                hash: hash.clone(),
+                status,
+                time,
+            };
+
+            let success = entry.is_success();
+            journal.record(entry)?;
+
+            match retry_after {
+                Some(retry_after) if retries < MAX_RATE_LIMIT_RETRIES => {
+                    retries += 1;
+                    eprintln!("Rate limited by the server. Pausing for {} seconds before retrying (attempt {} of {}).",
+                        retry_after.as_secs(), retries, MAX_RATE_LIMIT_RETRIES);
+                    std::thread::sleep(retry_after);
+                },
+                _ => {
+                    eprintln!();
+
+                    return Ok(RowOutcome {
+                        result: if success { RowResult::Sent } else { RowResult::Failed },
+                        status,
+                        error,
+                    });
+                },
+            }
+        }
+    }
+
+    /// Counts the result of a row and writes it to the report.
+    fn record(&mut self, record: ReportRecord) -> anyhow::Result<()> {
+        match record.result {
+            RowResult::Sent => self.summary.sent += 1,
+            RowResult::Skipped | RowResult::Simulated => self.summary.skipped += 1,
+            RowResult::Failed => self.summary.failed += 1,
+        }
+
+        match &mut self.report {
+            Some(report) => report.write(&record),
+            None => Ok(()),
+        }
+    }
+
+    /// Prints the summary. Fails if any row failed, so that the program exits with a non-zero status.
+    fn finish(self) -> anyhow::Result<()> {
+        eprintln!("Done. Sent: {}, skipped: {}, failed: {}", self.summary.sent, self.summary.skipped, self.summary.failed);
+
+        if self.summary.failed > 0 {
+            bail!("{} rows failed", self.summary.failed);
+        }
+
+        Ok(())
+    }
+}
+
+/// Describes the locked title or thumbnail (the `l` flag in `view <VIDEO_ID> main`) that is different
+/// from `submission`, if the video has one.
+fn locked_submission(branding: &MainApiResponse, submission: &RowSubmission) -> Option<String> {
+    match submission {
+        RowSubmission::Title(title) => branding.titles.iter()
+            .find(|locked| Flags::of_main_title(locked).locked && locked.title != *title)
+            .map(|locked| format!("title \"{}\"", locked.title)),
+        RowSubmission::Thumbnail(timestamp) => branding.thumbnails.iter()
+            .find(|locked| {
+                let same = if locked.original { timestamp.is_none() } else { timestamp.is_some() && locked.timestamp == *timestamp };
+                Flags::of_main_thumbnail(locked).locked && !same
+            })
+            .map(|locked| match locked.timestamp {
+                Some(timestamp) if !locked.original => format!("thumbnail at {}", timestamp),
+                _ => String::from("original thumbnail"),
+            }),
+        RowSubmission::Casual(_) => None,
+    }
+}
+
+/// The original title submitted to DeArrow, if there is one.
+fn dearrow_original_title(branding: &MainApiResponse) -> Option<String> {
+    branding.titles.iter().find(|title| title.original).map(|title| title.title.clone())
+}
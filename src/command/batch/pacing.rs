@@ -0,0 +1,205 @@
+// dearrow-cli - program to view and vote for DeArrow submissions
+// Copyright (C) 2024  mschae23
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Pacing of the requests sent by `batch`.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+
+use crate::utils;
+
+const HOUR_MILLIS: i64 = 60 * 60 * 1000;
+
+/// Used when a 429 response has no valid `Retry-After` header.
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// Waits between requests to respect `--min-interval` and `--max-per-hour`.
+///
+/// All times are in milliseconds since the Unix epoch, like in the journal.
+pub struct Pacer {
+    min_interval: Option<Duration>,
+    max_per_hour: Option<u32>,
+    last: Option<i64>,
+    /// The times of the requests sent in the last hour, oldest first.
+    recent: VecDeque<i64>,
+}
+
+impl Pacer {
+    /// Creates a pacer that takes the requests sent at the given times (like in a previous run) into account.
+    pub fn new(min_interval: Option<Duration>, max_per_hour: Option<u32>, history: impl IntoIterator<Item = i64>) -> Self {
+        let mut history = history.into_iter().collect::<Vec<_>>();
+        history.sort_unstable();
+
+        Pacer {
+            min_interval,
+            max_per_hour,
+            last: history.last().copied(),
+            recent: history.into(),
+        }
+    }
+
+    /// Blocks until the next request can be sent.
+    pub fn wait(&mut self) {
+        let now = Utc::now().timestamp_millis();
+        let (next, limited) = self.next_allowed(now);
+
+        if let (true, Some(max_per_hour)) = (limited, self.max_per_hour) {
+            eprintln!("Reached the limit of {} requests per hour, pausing until {}.", max_per_hour,
+                DateTime::from_timestamp_millis(next).map_or(next.to_string(), utils::render_datetime));
+        }
+
+        if next > now {
+            std::thread::sleep(Duration::from_millis((next - now) as u64));
+        }
+    }
+
+    /// The earliest time the next request can be sent at, if it is now `now`, and whether that is
+    /// later than `--min-interval` alone would require because of `--max-per-hour`.
+    fn next_allowed(&mut self, now: i64) -> (i64, bool) {
+        let mut next = now;
+
+        if let (Some(min_interval), Some(last)) = (self.min_interval, self.last) {
+            next = next.max(last + min_interval.as_millis() as i64);
+        }
+
+        if let Some(max_per_hour) = self.max_per_hour {
+            while self.recent.front().is_some_and(|time| *time <= now - HOUR_MILLIS) {
+                self.recent.pop_front();
+            }
+
+            if self.recent.len() >= max_per_hour as usize {
+                // Wait until enough of the requests in the window are older than an hour
+                let window_next = self.recent[self.recent.len() - max_per_hour as usize] + HOUR_MILLIS;
+
+                if window_next > next {
+                    return (window_next, true);
+                }
+            }
+        }
+
+        (next, false)
+    }
+
+    /// Records that a request was sent at `time`.
+    pub fn record(&mut self, time: i64) {
+        self.last = Some(time);
+        self.recent.push_back(time);
+    }
+}
+
+/// How long to wait after a 429 response, according to its `Retry-After` header
+/// (either a number of seconds or an HTTP date).
+pub fn retry_after(headers: &HeaderMap) -> Duration {
+    let Some(value) = headers.get(RETRY_AFTER).and_then(|value| value.to_str().ok()) else {
+        return DEFAULT_RETRY_AFTER;
+    };
+
+    if let Ok(seconds) = value.trim().parse::<u64>() {
+        return Duration::from_secs(seconds);
+    }
+
+    match DateTime::parse_from_rfc2822(value.trim()) {
+        Ok(date) => (date.with_timezone(&Utc) - Utc::now()).to_std().unwrap_or(Duration::ZERO),
+        Err(_) => DEFAULT_RETRY_AFTER,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::header::HeaderValue;
+    use super::*;
+
+    const NOW: i64 = 1_700_000_000_000;
+    const MINUTE_MILLIS: i64 = 60 * 1000;
+
+    fn retry_after_header(value: Option<&str>) -> Duration {
+        let mut headers = HeaderMap::new();
+
+        if let Some(value) = value {
+            headers.insert(RETRY_AFTER, HeaderValue::from_str(value).unwrap());
+        }
+
+        retry_after(&headers)
+    }
+
+    #[test]
+    fn no_limits() {
+        let mut pacer = Pacer::new(None, None, [NOW - 1]);
+        assert_eq!(pacer.next_allowed(NOW), (NOW, false));
+    }
+
+    #[test]
+    fn min_interval() {
+        let mut pacer = Pacer::new(Some(Duration::from_secs(10)), None, []);
+        assert_eq!(pacer.next_allowed(NOW), (NOW, false));
+
+        pacer.record(NOW - 3000);
+        assert_eq!(pacer.next_allowed(NOW), (NOW + 7000, false));
+        assert_eq!(pacer.next_allowed(NOW + 20000), (NOW + 20000, false));
+    }
+
+    #[test]
+    fn max_per_hour_window() {
+        // The history is sorted, so the oldest request determines when the window frees up
+        let mut pacer = Pacer::new(None, Some(2), [NOW - 10 * MINUTE_MILLIS, NOW - 50 * MINUTE_MILLIS]);
+        assert_eq!(pacer.next_allowed(NOW), (NOW + 10 * MINUTE_MILLIS, true));
+
+        // Once the oldest request is more than an hour ago, there is room for one more
+        assert_eq!(pacer.next_allowed(NOW + 10 * MINUTE_MILLIS), (NOW + 10 * MINUTE_MILLIS, false));
+
+        pacer.record(NOW + 10 * MINUTE_MILLIS);
+        assert_eq!(pacer.next_allowed(NOW + 10 * MINUTE_MILLIS), (NOW + 50 * MINUTE_MILLIS, true));
+    }
+
+    #[test]
+    fn max_per_hour_ignores_old_requests() {
+        let mut pacer = Pacer::new(None, Some(2), [NOW - 70 * MINUTE_MILLIS, NOW - 61 * MINUTE_MILLIS, NOW - 10 * MINUTE_MILLIS]);
+        assert_eq!(pacer.next_allowed(NOW), (NOW, false));
+    }
+
+    #[test]
+    fn min_interval_and_max_per_hour() {
+        let mut pacer = Pacer::new(Some(Duration::from_secs(30 * 60)), Some(1), [NOW - 50 * MINUTE_MILLIS]);
+        assert_eq!(pacer.next_allowed(NOW), (NOW + 10 * MINUTE_MILLIS, true));
+
+        let mut pacer = Pacer::new(Some(Duration::from_secs(30 * 60)), Some(2), [NOW - 50 * MINUTE_MILLIS, NOW - 5 * MINUTE_MILLIS]);
+        assert_eq!(pacer.next_allowed(NOW), (NOW + 25 * MINUTE_MILLIS, false));
+    }
+
+    #[test]
+    fn retry_after_seconds() {
+        assert_eq!(retry_after_header(Some("120")), Duration::from_secs(120));
+        assert_eq!(retry_after_header(Some(" 0 ")), Duration::ZERO);
+    }
+
+    #[test]
+    fn retry_after_date() {
+        let date = (Utc::now() + chrono::Duration::seconds(120)).to_rfc2822();
+        let duration = retry_after_header(Some(&date));
+        assert!(duration <= Duration::from_secs(120) && duration > Duration::from_secs(100), "{:?}", duration);
+
+        assert_eq!(retry_after_header(Some("Wed, 21 Oct 2015 07:28:00 GMT")), Duration::ZERO);
+    }
+
+    #[test]
+    fn retry_after_default() {
+        assert_eq!(retry_after_header(None), DEFAULT_RETRY_AFTER);
+        assert_eq!(retry_after_header(Some("soon")), DEFAULT_RETRY_AFTER);
+        assert_eq!(retry_after_header(Some("-5")), DEFAULT_RETRY_AFTER);
+    }
+}
@@ -0,0 +1,140 @@
+// dearrow-cli - program to view and vote for DeArrow submissions
+// Copyright (C) 2024  mschae23
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! The per-row result report of `batch` (`--report`).
+
+use std::fs::{File, OpenOptions};
+use std::path::Path;
+use anyhow::Context;
+use serde::Serialize;
+
+#[derive(Serialize, Copy, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RowResult {
+    Sent,
+    /// Already sent in a previous run, or skipped at the prompt.
+    Skipped,
+    Failed,
+    /// Not sent because of `--simulate` or `--dry-run`.
+    Simulated,
+}
+
+/// The result of sending the request for a row.
+#[derive(Clone, Debug)]
+pub struct RowOutcome {
+    pub result: RowResult,
+    /// `None` if no response was received.
+    pub status: Option<u16>,
+    /// The response body for unsuccessful responses, or the error if no response was received.
+    pub error: Option<String>,
+}
+
+impl RowOutcome {
+    pub fn skipped() -> Self {
+        RowOutcome {
+            result: RowResult::Skipped,
+            status: None,
+            error: None,
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ReportRecord {
+    pub line: u64,
+    pub video: String,
+    pub kind: &'static str,
+    /// The current title of the video on YouTube.
+    pub original_title: Option<String>,
+    /// The submitted title, thumbnail timestamp (or `original`) or casual categories.
+    pub submission: String,
+    pub downvote: bool,
+    pub result: RowResult,
+    pub status: Option<u16>,
+    pub error: Option<String>,
+    /// When the row was processed, in RFC 3339 format.
+    pub time: String,
+}
+
+pub struct Report {
+    writer: csv::Writer<File>,
+}
+
+impl Report {
+    /// Opens the report file at `path`. With `append` (when resuming a run), records are added to an existing report
+    /// instead of replacing it, and the header is only written if the file is new or empty.
+    pub fn open(path: &Path, append: bool) -> anyhow::Result<Self> {
+        let has_records = append && path.metadata().is_ok_and(|metadata| metadata.len() > 0);
+
+        let file = OpenOptions::new().write(true).create(true).append(append).truncate(!append).open(path)
+            .with_context(|| format!("Failed to create report file {}", path.display()))?;
+        let writer = csv::WriterBuilder::new().has_headers(!has_records).from_writer(file);
+
+        Ok(Report {
+            writer,
+        })
+    }
+
+    /// Writes a record and flushes it, so that the report is complete up to the last row even if the run is interrupted.
+    pub fn write(&mut self, record: &ReportRecord) -> anyhow::Result<()> {
+        self.writer.serialize(record).context("Failed to write report")?;
+        self.writer.flush().context("Failed to write report")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(line: u64) -> ReportRecord {
+        ReportRecord {
+            line,
+            video: String::from("dQw4w9WgXcQ"),
+            kind: "title",
+            original_title: None,
+            submission: String::from("Some title"),
+            downvote: false,
+            result: RowResult::Sent,
+            status: Some(200),
+            error: None,
+            time: String::from("2024-01-01T00:00:00Z"),
+        }
+    }
+
+    #[test]
+    fn appends_when_resuming() {
+        let path = std::env::temp_dir().join(format!("dearrow-cli-test-{}-report.csv", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        Report::open(&path, true).unwrap().write(&record(2)).unwrap();
+        Report::open(&path, true).unwrap().write(&record(3)).unwrap();
+        let appended = std::fs::read_to_string(&path).unwrap();
+
+        Report::open(&path, false).unwrap().write(&record(4)).unwrap();
+        let replaced = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let lines = appended.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("line,video,kind,"));
+        assert!(lines[1].starts_with("2,"));
+        assert!(lines[2].starts_with("3,"));
+
+        let lines = replaced.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[1].starts_with("4,"));
+    }
+}
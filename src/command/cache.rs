@@ -0,0 +1,53 @@
+// dearrow-cli - program to view and vote for DeArrow submissions
+// Copyright (C) 2024  mschae23
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{CacheAction, Options};
+
+pub fn run(options: Options, terminal_width: u16, action: CacheAction) -> anyhow::Result<()> {
+    let Some(dir) = options.cache.dir() else {
+        eprintln!("No cache directory available.");
+        return Ok(());
+    };
+
+    match action {
+        CacheAction::Clear {} => {
+            options.cache.clear()?;
+            eprintln!("Cleared cache in {}.", dir.display());
+        },
+        CacheAction::Stats {} => {
+            let mut builder = tabled::builder::Builder::new();
+            builder.push_record(["Endpoint", "Entries", "Expired", "Size", "TTL"]);
+
+            for stats in options.cache.stats()? {
+                builder.push_record([
+                    stats.endpoint.name().to_owned(),
+                    stats.entries.to_string(),
+                    stats.expired.to_string(),
+                    format!("{:.1} KiB", stats.bytes as f64 / 1024.0),
+                    format!("{} min", stats.endpoint.ttl().as_secs() / 60),
+                ]);
+            }
+
+            let table_settings = tabled::settings::Settings::default()
+                .with(tabled::settings::Style::psql())
+                .with(tabled::settings::Width::wrap(terminal_width as usize).priority(tabled::settings::peaker::PriorityMax::new(false)));
+
+            println!("Cache directory: {}\n\n{}", dir.display(), builder.build().with(table_settings));
+        },
+    }
+
+    Ok(())
+}
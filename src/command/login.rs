@@ -0,0 +1,42 @@
+// dearrow-cli - program to view and vote for DeArrow submissions
+// Copyright (C) 2024  mschae23
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anyhow::{Context, bail};
+
+use crate::{identity, Options};
+
+pub fn run_login(options: Options) -> anyhow::Result<()> {
+    let private_user_id = rpassword::prompt_password("Private user ID: ").context("Failed to read private user ID")?;
+    let private_user_id = private_user_id.trim();
+
+    if private_user_id.is_empty() {
+        bail!("Private user ID must not be empty");
+    }
+
+    let location = identity::store(&options, private_user_id)?;
+    eprintln!("Stored private user ID in {}.", location);
+    Ok(())
+}
+
+pub fn run_logout(options: Options) -> anyhow::Result<()> {
+    if identity::clear(&options)? {
+        eprintln!("Removed stored private user ID.");
+    } else {
+        eprintln!("No private user ID was stored.");
+    }
+
+    Ok(())
+}
@@ -18,3 +18,8 @@ pub mod vote;
 pub mod view;
 pub mod user;
 pub mod batch;
+pub mod login;
+pub mod warn;
+pub mod ban;
+pub mod cache;
+pub mod review;
@@ -0,0 +1,346 @@
+// dearrow-cli - program to view and vote for DeArrow submissions
+// Copyright (C) 2024  mschae23
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! A full-screen terminal UI to review and vote for the submissions on a video (`review`).
+
+use anyhow::Context;
+use dearrow_cli::api::main::{BrandingVote, MainApiClient, MainApiResponse, RequestPreview};
+use dearrow_cli::cache::CacheMode;
+use dearrow_cli::flags::Flags;
+use dearrow_cli::original_title::OriginalTitle;
+use dearrow_cli::VideoId;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Paragraph, Row, Table, TableState};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::{identity, Options};
+use super::{view, vote};
+
+const HELP: &str = "↑/↓ move  Tab switch table  u upvote  d downvote  l lock  t new title  r refresh  q quit";
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Focus {
+    Titles,
+    Thumbnails,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum VoteAction {
+    Upvote,
+    Downvote,
+    /// Upvote with auto-lock, even with `--no-autolock`.
+    Lock,
+}
+
+struct Review {
+    main_api: MainApiClient,
+    private_user_id: String,
+    video: VideoId,
+    no_autolock: bool,
+    using_casual: bool,
+    original_title: OriginalTitle,
+    branding: MainApiResponse,
+    focus: Focus,
+    titles: TableState,
+    thumbnails: TableState,
+    /// The new title being typed, if any.
+    input: Option<String>,
+    status: String,
+    /// The requests not sent because of `--dry-run`, printed after leaving the UI. `None` when not doing a dry run.
+    dry_run: Option<Vec<RequestPreview>>,
+}
+
+pub fn run(options: Options, client: reqwest::blocking::Client, video: VideoId, no_autolock: bool, using_casual: bool) -> anyhow::Result<()> {
+    let private_user_id = identity::private_user_id(&options)?;
+    // The data shown has to be current, since it is refreshed after every vote anyway
+    let main_api = options.main_api_client(&client)?.with_cache(options.cache.with_mode(CacheMode::Refresh));
+    let branding = main_api.get_branding(&video)?;
+    let original_title = options.original_titles(&client)?.get(&video, || branding.titles.iter()
+        .find(|title| title.original).map(|title| title.title.clone()));
+
+    let mut review = Review {
+        main_api,
+        private_user_id,
+        video,
+        no_autolock,
+        using_casual,
+        original_title,
+        branding,
+        focus: Focus::Titles,
+        titles: TableState::default().with_selected(Some(0)),
+        thumbnails: TableState::default().with_selected(Some(0)),
+        input: None,
+        status: String::new(),
+        dry_run: options.dry_run.then(Vec::new),
+    };
+
+    let mut terminal = ratatui::init();
+    let result = review.run(&mut terminal);
+    ratatui::restore();
+
+    for preview in review.dry_run.iter().flatten() {
+        eprintln!("Not sending request (dry run): {}\n", preview);
+    }
+
+    result
+}
+
+impl Review {
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> anyhow::Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame)).context("Failed to draw terminal UI")?;
+
+            if let Event::Key(key) = event::read().context("Failed to read terminal event")? {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+
+                if !self.handle_key(key, terminal)? {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    /// Returns `false` if the program should exit.
+    fn handle_key(&mut self, key: KeyEvent, terminal: &mut DefaultTerminal) -> anyhow::Result<bool> {
+        if let Some(input) = &mut self.input {
+            match key.code {
+                KeyCode::Enter => {
+                    let title = std::mem::take(input);
+                    self.input = None;
+
+                    if !title.trim().is_empty() {
+                        self.send(terminal, format!("Submitted title \"{}\"", title), BrandingVote {
+                            auto_lock: !self.no_autolock,
+                            casual_mode: self.using_casual,
+                            ..BrandingVote::title(self.private_user_id.clone(), self.video.to_string(), title)
+                        })?;
+                    }
+                },
+                KeyCode::Esc => self.input = None,
+                KeyCode::Backspace => {
+                    input.pop();
+                },
+                KeyCode::Char(c) => input.push(c),
+                _ => {},
+            }
+
+            return Ok(true);
+        }
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(false),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(false),
+            KeyCode::Up | KeyCode::Char('k') => self.selected_state().select_previous(),
+            KeyCode::Down | KeyCode::Char('j') => self.selected_state().select_next(),
+            KeyCode::Tab | KeyCode::BackTab => self.focus = match self.focus {
+                Focus::Titles => Focus::Thumbnails,
+                Focus::Thumbnails => Focus::Titles,
+            },
+            KeyCode::Char('u') => self.vote(terminal, VoteAction::Upvote)?,
+            KeyCode::Char('d') => self.vote(terminal, VoteAction::Downvote)?,
+            KeyCode::Char('l') => self.vote(terminal, VoteAction::Lock)?,
+            KeyCode::Char('t') => self.input = Some(String::new()),
+            KeyCode::Char('r') => {
+                self.refresh();
+
+                if self.status.is_empty() {
+                    self.status = String::from("Refreshed.");
+                }
+            },
+            _ => {},
+        }
+
+        Ok(true)
+    }
+
+    fn selected_state(&mut self) -> &mut TableState {
+        match self.focus {
+            Focus::Titles => &mut self.titles,
+            Focus::Thumbnails => &mut self.thumbnails,
+        }
+    }
+
+    /// Votes for the selected title or thumbnail.
+    fn vote(&mut self, terminal: &mut DefaultTerminal, action: VoteAction) -> anyhow::Result<()> {
+        let selected = self.selected_state().selected();
+
+        let (request, name) = match self.focus {
+            Focus::Titles => {
+                let Some(title) = selected.and_then(|index| self.branding.titles.get(index)) else {
+                    return Ok(());
+                };
+
+                (BrandingVote::title(self.private_user_id.clone(), self.video.to_string(), title.title.clone()), format!("title \"{}\"", title.title))
+            },
+            Focus::Thumbnails => {
+                let Some(thumbnail) = selected.and_then(|index| self.branding.thumbnails.get(index)) else {
+                    return Ok(());
+                };
+
+                let timestamp = match vote::thumbnail_timestamp(thumbnail.original, thumbnail.timestamp, &thumbnail.uuid) {
+                    Ok(timestamp) => timestamp,
+                    Err(err) => {
+                        self.status = format!("{:#}", err);
+                        return Ok(());
+                    },
+                };
+
+                (BrandingVote::thumbnail(self.private_user_id.clone(), self.video.to_string(), timestamp),
+                    timestamp.map_or(String::from("original thumbnail"), |timestamp| format!("thumbnail at {}", timestamp)))
+            },
+        };
+
+        let (verb, downvote, auto_lock) = match action {
+            VoteAction::Upvote => ("Upvoted", false, !self.no_autolock),
+            VoteAction::Downvote => ("Downvoted", true, !self.no_autolock),
+            VoteAction::Lock => ("Locked", false, true),
+        };
+
+        self.send(terminal, format!("{} {}", verb, name), BrandingVote {
+            downvote,
+            auto_lock,
+            casual_mode: self.using_casual,
+            ..request
+        })
+    }
+
+    /// Sends a vote and refreshes the data afterwards. Errors are shown in the status line instead of exiting.
+    fn send(&mut self, terminal: &mut DefaultTerminal, description: String, request: BrandingVote) -> anyhow::Result<()> {
+        if let Some(previews) = &mut self.dry_run {
+            previews.push(self.main_api.preview_branding(&request)?);
+            self.status = format!("Dry run: {} (not sent; the requests are printed when quitting)", description);
+            return Ok(());
+        }
+
+        self.status = String::from("Sending...");
+        terminal.draw(|frame| self.draw(frame)).context("Failed to draw terminal UI")?;
+
+        self.status = match self.main_api.post_branding(&request) {
+            Ok(response) if response.status().is_success() => format!("{}. Response: {}", description, response.status()),
+            Ok(response) => {
+                let status = response.status();
+                format!("Server returned error {}: {}", status, response.text().unwrap_or_default().trim())
+            },
+            Err(err) => format!("{:#}", err),
+        };
+
+        let status = std::mem::take(&mut self.status);
+        self.refresh();
+        self.status = if self.status.is_empty() { status } else { format!("{} ({})", status, self.status) };
+        Ok(())
+    }
+
+    /// Fetches the branding data again. Errors are shown in the status line, keeping the old data.
+    fn refresh(&mut self) {
+        match self.main_api.get_branding(&self.video) {
+            Ok(branding) => {
+                self.branding = branding;
+                self.status = String::new();
+            },
+            Err(err) => self.status = format!("Failed to refresh: {:#}", err),
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [header_area, titles_area, thumbnails_area, status_area, help_area] = Layout::vertical([
+            Constraint::Length(2),
+            Constraint::Fill(1),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ]).areas(frame.area());
+
+        let original_title = match (self.original_title.title(), self.original_title.note()) {
+            (Some(title), Some(note)) => format!("Original title: {} ({})", title, note),
+            (Some(title), None) => format!("Original title: {}", title),
+            (None, Some(note)) => format!("Original title: ({})", note),
+            (None, None) => String::new(),
+        };
+
+        frame.render_widget(Paragraph::new(vec![
+            Line::from(format!("Reviewing https://www.youtube.com/watch?v={}", self.video)).bold(),
+            Line::from(original_title),
+        ]), header_area);
+
+        self.draw_titles(frame, titles_area);
+        self.draw_thumbnails(frame, thumbnails_area);
+
+        let status = match &self.input {
+            Some(input) => Line::from(format!("New title: {}▏", input)).yellow(),
+            None => Line::from(self.status.as_str()),
+        };
+
+        frame.render_widget(Paragraph::new(status), status_area);
+        frame.render_widget(Paragraph::new(Line::from(if self.input.is_some() { "Enter submit  Esc cancel" } else { HELP }).dim()), help_area);
+    }
+
+    fn draw_titles(&mut self, frame: &mut Frame, area: Rect) {
+        let score_length = self.score_length();
+        let rows = self.branding.titles.iter().enumerate().map(|(i, title)| Row::new([
+            (i + 1).to_string(),
+            title.title.clone(),
+            view::format_score(format!("{:>width$}", title.votes, width = score_length), Flags::of_main_title(title)),
+            title.uuid.clone(),
+            title.user_id.clone(),
+        ]));
+
+        let table = submissions_table(rows, "Title", "Titles", self.focus == Focus::Titles);
+        frame.render_stateful_widget(table, area, &mut self.titles);
+    }
+
+    fn draw_thumbnails(&mut self, frame: &mut Frame, area: Rect) {
+        let score_length = self.score_length();
+        let rows = self.branding.thumbnails.iter().enumerate().map(|(i, thumbnail)| Row::new([
+            (i + 1).to_string(),
+            if let Some(timestamp) = thumbnail.timestamp { timestamp.to_string() } else { String::from("Original") },
+            view::format_score(format!("{:>width$}", thumbnail.votes, width = score_length), Flags::of_main_thumbnail(thumbnail)),
+            thumbnail.uuid.clone(),
+            thumbnail.user_id.clone(),
+        ]));
+
+        let table = submissions_table(rows, "Thumbnail", "Thumbnails", self.focus == Focus::Thumbnails);
+        frame.render_stateful_widget(table, area, &mut self.thumbnails);
+    }
+
+    /// The width of the widest score of all titles and thumbnails, so that both tables are aligned.
+    fn score_length(&self) -> usize {
+        self.branding.titles.iter().map(|title| title.votes)
+            .chain(self.branding.thumbnails.iter().map(|thumbnail| thumbnail.votes))
+            .map(|votes| votes.to_string().len())
+            .max().unwrap_or(1)
+    }
+}
+
+fn submissions_table<'a>(rows: impl IntoIterator<Item = Row<'a>>, column: &'a str, title: &'a str, focused: bool) -> Table<'a> {
+    let block = Block::bordered().title(format!(" {} ", title));
+    let block = if focused { block.border_style(Style::new().bold()) } else { block.border_style(Style::new().dim()) };
+
+    Table::new(rows, [
+        Constraint::Length(3),
+        Constraint::Fill(3),
+        Constraint::Length(12),
+        Constraint::Length(36),
+        Constraint::Fill(1),
+    ])
+        .header(Row::new(["#", column, "Score", "UUID", "User ID"]).bold())
+        .block(block)
+        .row_highlight_style(if focused { Style::new().reversed() } else { Style::new().underlined() })
+}
@@ -14,51 +14,127 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use anyhow::{Context, bail};
-use chrono::DateTime;
+use chrono::{DateTime, NaiveDate, NaiveTime};
 use dearrow_browser_api::string::{ApiWarning, Extension};
-use reqwest::Url;
+use dearrow_cli::flags::Flags;
 
-use crate::{Options, UserSubcommand, WarningKind, utils};
+use crate::{Options, OutputFormat, UserSubcommand, UserSubmissionKind, WarningKind, utils};
+use crate::command::view;
+use crate::output::{self, SubmissionRecord, WarningRecord};
 
 pub fn run(options: Options, client: reqwest::blocking::Client, terminal_width: u16, user: String, subcommand: UserSubcommand) -> anyhow::Result<()> {
     match subcommand {
         UserSubcommand::Warnings { kind, newest, } => {
             let issued = match kind { WarningKind::Issued => true, WarningKind::Received => false, };
 
-            let url = Url::parse(&format!("{}warnings/user_id/{}/{}", &options.browser_api, user, kind.name()))?;
-            let response = client.get(url).header("User-Agent", crate::USER_AGENT).send().context("Failed to send branding request")?;
+            let warnings = options.browser_api_client(&client)?.warnings_by_user(&user, kind)?;
+            let warnings_len = warnings.len();
 
-            if response.status() != 200 {
-                bail!("Failed to get thumbnails. Response: {}\n{}", response.status(), response.text()?);
+            if options.format != OutputFormat::Table {
+                let warnings = warnings.iter().take(if newest != 0 { newest } else { warnings_len })
+                    .map(WarningRecord::from).collect::<Vec<_>>();
+                return output::write_list(&mut std::io::stdout(), options.format, &warnings);
             }
 
-            let warnings: Vec<ApiWarning> = response.json()?;
-            let warnings_len = warnings.len();
+            println!("{}", warnings_table(warnings.into_iter().take(if newest != 0 { newest } else { warnings_len }), issued, terminal_width));
 
-            let mut builder = tabled::builder::Builder::new();
-            builder.push_record(["Message", "Timestamp", "Extension", "Active", if issued { "Warned" } else { "Issuer" },]);
-
-            for warning in warnings.into_iter().take(if newest != 0 { newest } else { warnings_len }) {
-                builder.push_record([
-                    warning.message.to_string(),
-                    DateTime::from_timestamp_millis(warning.time_issued).map_or(warning.time_issued.to_string(), utils::render_datetime),
-                    match warning.extension { Extension::SponsorBlock => String::from("SB"), Extension::DeArrow => String::from("DeArrow"), },
-                    warning.active.to_string(),
-                    if issued { warning.warned_user_id.to_string() } else { warning.issuer_user_id.to_string() },
-                ]);
-            }
+            Ok(())
+        },
+        UserSubcommand::Submissions { kind, newest, since, until, flags, exclude_flags, } => {
+            let browser_api = options.browser_api_client(&client)?;
+            let filter = SubmissionFilter {
+                since: since.map(start_of_day),
+                until: until.and_then(|until| until.succ_opt()).map(start_of_day),
+                flags: flags.unwrap_or_default(),
+                exclude_flags: exclude_flags.unwrap_or_default(),
+            };
+
+            match kind {
+                UserSubmissionKind::Titles => {
+                    let mut titles = browser_api.titles_by_user(&user)?;
+                    titles.sort_by(|a, b| a.time_submitted.cmp(&b.time_submitted).reverse());
+                    titles.retain(|title| filter.matches(title.time_submitted, Flags::of_title(title)));
+
+                    if newest != 0 {
+                        titles.truncate(newest);
+                    }
+
+                    if options.format != OutputFormat::Table {
+                        let titles = titles.iter().map(SubmissionRecord::from_title).collect::<Vec<_>>();
+                        return output::write_list(&mut std::io::stdout(), options.format, &titles);
+                    }
+
+                    println!("{}", view::titles_table(titles, terminal_width, true, None));
+                },
+                UserSubmissionKind::Thumbnails => {
+                    let mut thumbnails = browser_api.thumbnails_by_user(&user)?;
+                    thumbnails.sort_by(|a, b| a.time_submitted.cmp(&b.time_submitted).reverse());
+                    // The o flag is not shown for thumbnails, but it should still be possible to filter by it
+                    thumbnails.retain(|thumbnail| filter.matches(thumbnail.time_submitted,
+                        Flags { original: thumbnail.original, ..Flags::of_thumbnail(thumbnail) }));
+
+                    if newest != 0 {
+                        thumbnails.truncate(newest);
+                    }
 
-            let table_settings = tabled::settings::Settings::default()
-                .with(tabled::settings::Style::psql().line_horizontal(
-                    tabled::settings::style::HorizontalLine::full('-', '+', ' ', ' '))
-                    .horizontals([(1, tabled::settings::style::HorizontalLine::full('=', ':', ' ', ' '))]))
-                .with(tabled::settings::Width::wrap(terminal_width as usize).priority(tabled::settings::peaker::PriorityLeft::new()));
+                    if options.format != OutputFormat::Table {
+                        let thumbnails = thumbnails.iter().map(SubmissionRecord::from_thumbnail).collect::<Vec<_>>();
+                        return output::write_list(&mut std::io::stdout(), options.format, &thumbnails);
+                    }
 
-            let table = builder.build().with(table_settings).to_string();
-            println!("{}", table);
+                    println!("{}", view::thumbnails_table(thumbnails, terminal_width, true, None));
+                },
+            }
 
             Ok(())
         },
     }
+}
+
+/// Builds the table of warnings. If `issued` is set, the warned user is shown instead of the issuer.
+pub fn warnings_table(warnings: impl IntoIterator<Item = ApiWarning>, issued: bool, terminal_width: u16) -> tabled::Table {
+    let mut builder = tabled::builder::Builder::new();
+    builder.push_record(["Message", "Timestamp", "Extension", "Active", if issued { "Warned" } else { "Issuer" },]);
+
+    for warning in warnings {
+        builder.push_record([
+            warning.message.to_string(),
+            DateTime::from_timestamp_millis(warning.time_issued).map_or(warning.time_issued.to_string(), utils::render_datetime),
+            match warning.extension { Extension::SponsorBlock => String::from("SB"), Extension::DeArrow => String::from("DeArrow"), },
+            warning.active.to_string(),
+            if issued { warning.warned_user_id.to_string() } else { warning.issuer_user_id.to_string() },
+        ]);
+    }
+
+    let table_settings = tabled::settings::Settings::default()
+        .with(tabled::settings::Style::psql().line_horizontal(
+            tabled::settings::style::HorizontalLine::full('-', '+', ' ', ' '))
+            .horizontals([(1, tabled::settings::style::HorizontalLine::full('=', ':', ' ', ' '))]))
+        .with(tabled::settings::Width::wrap(terminal_width as usize).priority(tabled::settings::peaker::PriorityLeft::new()));
+
+    let mut table = builder.build();
+    table.with(table_settings);
+    table
+}
+
+struct SubmissionFilter {
+    /// Inclusive, in milliseconds.
+    since: Option<i64>,
+    /// Exclusive, in milliseconds.
+    until: Option<i64>,
+    flags: Flags,
+    exclude_flags: Flags,
+}
+
+impl SubmissionFilter {
+    fn matches(&self, time_submitted: i64, flags: Flags) -> bool {
+        self.since.is_none_or(|since| time_submitted >= since)
+            && self.until.is_none_or(|until| time_submitted < until)
+            && flags.contains(self.flags)
+            && !flags.intersects(self.exclude_flags)
+    }
+}
+
+fn start_of_day(date: NaiveDate) -> i64 {
+    date.and_time(NaiveTime::MIN).and_utc().timestamp_millis()
 }
\ No newline at end of file
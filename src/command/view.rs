@@ -14,119 +14,155 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+use std::collections::{HashMap, HashSet};
 use std::io::Write;
-use anyhow::{Context, bail};
-use chrono::DateTime;
-use reqwest::Url;
-use serde::Deserialize;
+use std::time::Duration;
+use anyhow::bail;
+use chrono::{DateTime, Utc};
 use dearrow_browser_api::string::{ApiThumbnail, ApiTitle};
+use dearrow_cli::api::main::MainApiResponse;
+use dearrow_cli::cache::CacheMode;
+use dearrow_cli::flags::Flags;
+use dearrow_cli::VideoId;
+
+use crate::{Options, OutputFormat, SubmissionKind, utils};
+use crate::output::{self, CasualVoteRecord, MainSubmissionRecord, MergedSubmissionRecord, SubmissionRecord, VideoInfo};
+
+fn print_header(video: &VideoInfo, writer: &mut impl std::io::Write) -> anyhow::Result<()> {
+    write!(writer, "View on YouTube: https://www.youtube.com/watch?v={}\nUses DeArrow data licensed under CC BY-NC-SA 4.0 from https://dearrow.ajay.app/.\n",
+        video.video_id)?;
+
+    // Neither is set with --no-original-title
+    match (&video.original_title, &video.original_title_note) {
+        (Some(title), Some(note)) => write!(writer, "Original title: {} ({})\n", title, note)?,
+        (Some(title), None) => write!(writer, "Original title: {}\n", title)?,
+        (None, Some(note)) => write!(writer, "Original title: ({})\n", note)?,
+        (None, None) => {},
+    }
 
-use crate::{Options, SubmissionKind, OEmbedResponse, utils};
-
-#[derive(Deserialize, Clone, PartialEq, Eq)]
-pub struct MainApiTitle {
-    pub title: String,
-    pub original: bool,
-    pub votes: i32,
-    pub locked: bool,
-    #[serde(rename = "UUID")]
-    pub uuid: String,
-    #[serde(rename = "userID")]
-    pub user_id: String,
+    Ok(())
 }
 
-#[derive(Deserialize, Clone, PartialEq)]
-pub struct MainApiThumbnail {
-    pub timestamp: Option<f64>,
-    pub original: bool,
-    pub votes: i32,
-    pub locked: bool,
-    #[serde(rename = "UUID")]
-    pub uuid: String,
-    #[serde(rename = "userID")]
-    pub user_id: String,
+/// Gets the general information about a video, including the data only available from the main API.
+fn get_branding_info(options: &Options, client: &reqwest::blocking::Client, video: &VideoId, response: &MainApiResponse) -> anyhow::Result<VideoInfo> {
+    let fallback_title = response.titles.iter().find(|title| title.original).map(|title| title.title.as_str());
+    let mut info = get_video_info(options, client, video, fallback_title)?;
+    info.video_duration = response.video_duration;
+    info.random_time = response.random_time;
+    info.random_timestamp = response.random_time.zip(response.video_duration).map(|(random_time, video_duration)| random_time * video_duration);
+    info.casual_votes = response.casual_votes.iter().map(CasualVoteRecord::from).collect();
+    Ok(info)
 }
 
-#[derive(Deserialize, Clone, PartialEq)]
-pub struct MainApiCasualVote {
-    pub id: crate::CasualCategory,
-    pub count: i32,
-}
+/// Prints the video duration, random time and casual votes below the header.
+fn print_branding_info(video: &VideoInfo, writer: &mut impl std::io::Write) -> anyhow::Result<()> {
+    if let Some(video_duration) = video.video_duration {
+        write!(writer, "Video duration: {}\n", video_duration)?;
+    }
 
-#[derive(Deserialize, Clone, PartialEq)]
-pub struct MainApiResponse {
-    pub titles: Vec<MainApiTitle>,
-    pub thumbnails: Vec<MainApiThumbnail>,
-    #[serde(rename = "casualVotes")]
-    pub casual_votes: Vec<MainApiCasualVote>,
-
-    #[serde(rename = "randomTime")]
-    pub random_time: Option<f64>,
-    #[serde(rename = "videoDuration")]
-    pub video_duration: Option<f64>,
-}
+    if let Some(random_time) = video.random_time {
+        if let Some(random_timestamp) = video.random_timestamp {
+            write!(writer, "Random time: {} (timestamp: {})\n", random_time, random_timestamp)?;
+        } else {
+            write!(writer, "Random time: {}\n", random_time)?;
+        }
+    }
+
+    if !video.casual_votes.is_empty() {
+        write!(writer, "Casual votes: {}\n", video.casual_votes.iter()
+            .map(|vote| format!("{}x {}", vote.count, vote.category.name()))
+            .collect::<Vec<_>>().join(", "))?;
+    }
 
-fn get_original_title(client: &reqwest::blocking::Client, video: &str) -> anyhow::Result<String> {
-    let url = Url::parse_with_params(
-        "https://www.youtube-nocookie.com/oembed",
-        &[("url", format!("https://youtu.be/{}", video))]
-    ).context("Failed to construct an oembed request URL")?;
-    let resp: OEmbedResponse = client.get(url).header("User-Agent", crate::USER_AGENT)
-        .send().context("Failed to send oembed request")?
-        .json().context("Failed to deserialize oembed response")?;
-    resp.title.context("oembed response contained no title")
+    Ok(())
 }
 
-fn print_header(client: &reqwest::blocking::Client, video: &str, writer: &mut impl std::io::Write) -> anyhow::Result<()> {
-    Ok(write!(writer, "View on YouTube: https://www.youtube.com/watch?v={}\nUses DeArrow data licensed under CC BY-NC-SA 4.0 from https://dearrow.ajay.app/.\nOriginal title: {}\n",
-        video, get_original_title(client, video).context("Failed to get original title")?)?)
+/// Gets the general information about a video. `fallback_title` is the original title from DeArrow data, if any.
+fn get_video_info(options: &Options, client: &reqwest::blocking::Client, video: &VideoId, fallback_title: Option<&str>) -> anyhow::Result<VideoInfo> {
+    let original_title = options.original_titles(client)?.get(video, || fallback_title.map(str::to_owned));
+
+    Ok(VideoInfo {
+        video_id: video.to_string(),
+        original_title_note: original_title.note(),
+        original_title: original_title.into_title(),
+        video_duration: None,
+        random_time: None,
+        random_timestamp: None,
+        casual_votes: Vec::new(),
+    })
 }
 
-fn make_request(client: &reqwest::blocking::Client, url: Url) -> anyhow::Result<reqwest::blocking::Response> {
-    let response = client.get(url).header("User-Agent", crate::USER_AGENT).send().context("Failed to send request")?;
+/// Prints the submissions on a video. With `--watch`, fetches them again every `interval` seconds.
+pub fn run(options: Options, client: reqwest::blocking::Client, terminal_width: u16, video: VideoId, kind: SubmissionKind, watch: Option<u64>) -> anyhow::Result<()> {
+    let Some(interval) = watch else {
+        return show(&options, &client, terminal_width, &video, kind, None, &mut std::io::stdout());
+    };
 
-    if response.status() != 200 {
-        bail!("Failed to get submissions. Response: {}\n{}", response.status(), response.text()?);
+    if options.format != OutputFormat::Table {
+        bail!("--watch only supports table output");
     }
 
-    Ok(response)
+    let mut watch = Watch::default();
+    let mut last = Vec::new();
+    let mut stdout = std::io::stdout();
+
+    loop {
+        let mut buf = Vec::new();
+        let error = match show(&options, &client, terminal_width, &video, kind, Some(&mut watch), &mut buf) {
+            Ok(()) => {
+                last = buf;
+                None
+            },
+            Err(err) => {
+                // Keep showing the data of the last successful fetch
+                watch.discard();
+                Some(err)
+            },
+        };
+
+        // Clear the screen and move the cursor to the top left corner
+        write!(stdout, "\x1b[2J\x1b[H")?;
+        stdout.write_all(&last)?;
+
+        if let Some(err) = error {
+            write!(stdout, "\nFailed to refresh: {:#}\n", err)?;
+        }
+
+        write!(stdout, "\nLast refresh: {} UTC. Refreshing every {} seconds, press Ctrl+C to stop.\n", utils::render_datetime(Utc::now()), interval)?;
+        write!(stdout, "Changes since the previous refresh are marked in the first column: + new, * changed score, lock state or flags.\n")?;
+        stdout.flush()?;
+
+        std::thread::sleep(Duration::from_secs(interval));
+    }
 }
 
-pub fn run(options: Options, client: reqwest::blocking::Client, terminal_width: u16, video: String, kind: SubmissionKind) -> anyhow::Result<()> {
+/// Writes the submissions on a video. With `watch`, the data is always fetched again and changes are marked.
+fn show(options: &Options, client: &reqwest::blocking::Client, terminal_width: u16, video: &VideoId, kind: SubmissionKind,
+        mut watch: Option<&mut Watch>, writer: &mut impl Write) -> anyhow::Result<()> {
+    // The original title can still come from the cache, since it rarely changes
+    let cache = if watch.is_some() { options.cache.with_mode(CacheMode::Refresh) } else { options.cache.clone() };
+    let watching = watch.is_some();
+
     match kind {
         SubmissionKind::Main => {
-            let url = Url::parse(&format!("{}branding?returnUserID=true&fetchAll=true&videoID={}",
-                &options.main_api, &video))?;
-            let response = make_request(&client, url)?;
-
-            let response: MainApiResponse = response.json()?;
+            let response = options.main_api_client(client)?.with_cache(cache).get_branding(video)?;
             let _titles_len = response.titles.len();
 
-            let mut stdout = std::io::stdout();
-            print_header(&client, &video, &mut stdout)?;
-
-            if let Some(video_duration) = response.video_duration {
-                write!(stdout, "Video duration: {}\n", video_duration)?;
-            }
+            let info = get_branding_info(options, client, video, &response)?;
 
-            if let Some(random_time) = response.random_time {
-                if let Some(video_duration) = response.video_duration {
-                    write!(stdout, "Random time: {} (timestamp: {})\n", random_time, random_time * video_duration)?;
-                } else {
-                    write!(stdout, "Random time: {}\n", random_time)?;
-                }
+            if options.format != OutputFormat::Table {
+                let titles = response.titles.iter().map(|title| MainSubmissionRecord::from_title(video.as_str(), title)).collect::<Vec<_>>();
+                let thumbnails = response.thumbnails.iter().map(|thumbnail| MainSubmissionRecord::from_thumbnail(video.as_str(), thumbnail)).collect::<Vec<_>>();
+                return output::write_video(writer, options.format, &info, Some(&titles), Some(&thumbnails));
             }
 
-            if !response.casual_votes.is_empty() {
-                write!(stdout, "Casual votes: {}\n", response.casual_votes.into_iter()
-                    .map(|vote| format!("{}x {}", vote.count, vote.id.name()))
-                    .collect::<Vec<_>>().join(", "))?;
-            }
+            print_header(&info, writer)?;
+            print_branding_info(&info, writer)?;
 
             let mut titles_builder = tabled::builder::Builder::new();
             let mut thumbnails_builder = tabled::builder::Builder::new();
-            titles_builder.push_record(["Title", "Score", "UUID", "User ID"]);
-            thumbnails_builder.push_record(["Thumbnail", "Score", "UUID", "User ID"]);
+            titles_builder.push_record(with_change_column(vec!["#", "Title", "Score", "UUID", "User ID"], "", watching));
+            thumbnails_builder.push_record(with_change_column(vec!["#", "Thumbnail", "Score", "UUID", "User ID"], "", watching));
 
             let mut score_length = 1;
 
@@ -138,64 +174,33 @@ pub fn run(options: Options, client: reqwest::blocking::Client, terminal_width:
                 score_length = score_length.max(if thumbnail.votes == 0 { 1 } else { thumbnail.votes.abs().ilog10() + 1 + (thumbnail.votes.is_negative() as u32) });
             }
 
-            for title in response.titles {
-                let mut score = format!("{:>width$}", title.votes, width = score_length as usize);
-                let mut flags = String::new();
+            for (i, title) in response.titles.into_iter().enumerate() {
+                let flags = Flags::of_main_title(&title);
+                let score = format_score(format!("{:>width$}", title.votes, width = score_length as usize), flags);
+                let change = watch.as_deref_mut().map_or("", |watch| watch.mark(&title.uuid, format!("title \"{}\"", title.title), i64::from(title.votes), flags));
 
-                if title.original {
-                    flags.push('o');
-                }
-
-                if !title.locked && title.votes < 0 {
-                    flags.push('h'); // Title should only appear in submission menus
-                }
-
-                if title.locked {
-                    flags.push('l'); // Locked by a VIP
-                }
-
-                if !flags.is_empty() {
-                    score.reserve(2 + flags.len());
-                    score.push_str(", ");
-                    score.push_str(&flags);
-                }
-
-                titles_builder.push_record([
+                titles_builder.push_record(with_change_column(vec![
+                    (i + 1).to_string(),
                     title.title.to_string(),
                     score,
                     title.uuid.to_string(),
                     title.user_id.to_string(),
-                ]);
+                ], change.to_owned(), watching));
             }
 
-            for thumbnail in response.thumbnails {
-                let mut score = format!("{:>width$}", thumbnail.votes, width = score_length as usize);
-                let mut flags = String::new();
-
-                if thumbnail.original {
-                    flags.push('o');
-                }
-
-                if !thumbnail.locked && thumbnail.votes < 0 {
-                    flags.push('h'); // Title should only appear in submission menus
-                }
-
-                if thumbnail.locked {
-                    flags.push('l'); // Locked by a VIP
-                }
-
-                if !flags.is_empty() {
-                    score.reserve(2 + flags.len());
-                    score.push_str(", ");
-                    score.push_str(&flags);
-                }
+            for (i, thumbnail) in response.thumbnails.into_iter().enumerate() {
+                let flags = Flags::of_main_thumbnail(&thumbnail);
+                let score = format_score(format!("{:>width$}", thumbnail.votes, width = score_length as usize), flags);
+                let timestamp = if let Some (timestamp) = thumbnail.timestamp { timestamp.to_string() } else { String::from("Original") };
+                let change = watch.as_deref_mut().map_or("", |watch| watch.mark(&thumbnail.uuid, format!("thumbnail {}", timestamp), i64::from(thumbnail.votes), flags));
 
-                thumbnails_builder.push_record([
-                    if let Some (timestamp) = thumbnail.timestamp { timestamp.to_string() } else { String::from("Original") },
+                thumbnails_builder.push_record(with_change_column(vec![
+                    (i + 1).to_string(),
+                    timestamp,
                     score,
                     thumbnail.uuid.to_string(),
                     thumbnail.user_id.to_string(),
-                ]);
+                ], change.to_owned(), watching));
             }
 
             let table_settings = tabled::settings::Settings::default()
@@ -206,185 +211,348 @@ pub fn run(options: Options, client: reqwest::blocking::Client, terminal_width:
             let titles_table = titles_builder.build().with(table_settings.clone()).to_string();
             let thumbnails_table = thumbnails_builder.build().with(table_settings).to_string();
 
-            write!(stdout, "\n{}\n\n{}\n", titles_table, thumbnails_table)?;
+            write!(writer, "\n{}\n\n{}\n", titles_table, thumbnails_table)?;
         },
         SubmissionKind::Title => {
-            let url = Url::parse(&options.browser_api)?.join("titles/video_id/")?.join(&video)?;
-            let response = make_request(&client, url)?;
-
-            let mut titles: Vec<ApiTitle> = response.json()?;
-            let titles_len = titles.len();
+            let mut titles = options.browser_api_client(client)?.with_cache(cache).titles_by_video(video)?;
             titles.sort_by(|a, b| a.time_submitted.cmp(&b.time_submitted).reverse());
 
-            let mut stdout = std::io::stdout();
-            print_header(&client, &video, &mut stdout)?;
+            let fallback_title = titles.iter().find(|title| title.original).map(|title| &*title.title);
+            let info = get_video_info(options, client, video, fallback_title)?;
 
-            let mut builder = tabled::builder::Builder::new();
-            builder.push_record(["Submitted", "Title", "Score", "UUID", "Username", "User ID"]);
+            if options.format != OutputFormat::Table {
+                let titles = titles.iter().map(SubmissionRecord::from_title).collect::<Vec<_>>();
+                return output::write_video(writer, options.format, &info, Some(&titles), None);
+            }
 
-            let mut score_length = 1;
+            print_header(&info, writer)?;
+            write!(writer, "\n{}\n", titles_table(titles, terminal_width, false, watch.as_deref_mut()))?;
+        },
+        SubmissionKind::Thumbnail => {
+            let mut thumbnails = options.browser_api_client(client)?.with_cache(cache).thumbnails_by_video(video)?;
+            thumbnails.sort_by(|a, b| a.time_submitted.cmp(&b.time_submitted).reverse());
 
-            for title in &titles {
-                score_length = score_length.max(if title.score == 0 { 1 } else { title.score.abs().ilog10() + 1 + (title.score.is_negative() as u32) })
-                    .max(if title.votes == 0 { 1 } else { title.votes.abs().ilog10() + 1 })
-                    .max(if title.downvotes == 0 { 1 } else { title.downvotes.abs().ilog10() + 1 });
+            let info = get_video_info(options, client, video, None)?;
+
+            if options.format != OutputFormat::Table {
+                let thumbnails = thumbnails.iter().map(SubmissionRecord::from_thumbnail).collect::<Vec<_>>();
+                return output::write_video(writer, options.format, &info, None, Some(&thumbnails));
             }
 
-            for title in titles {
-                let mut score = format!("{:>width$} ({:>+width$} | {})", title.score, title.votes,
-                    if title.downvotes == 0 {
-                        format!("{: >width$}-0", "", width = score_length.saturating_sub(2) as usize)
-                    } else {
-                        format!("{:->width$}", -title.downvotes, width = score_length as usize)
-                    }, width = score_length as usize);
+            print_header(&info, writer)?;
+            write!(writer, "\n{}\n", thumbnails_table(thumbnails, terminal_width, false, watch.as_deref_mut()))?;
+        },
+        SubmissionKind::All => {
+            let response = options.main_api_client(client)?.with_cache(cache.clone()).get_branding(video)?;
+            let browser_api = options.browser_api_client(client)?.with_cache(cache);
+            let mut titles = browser_api.titles_by_video(video)?;
+            let mut thumbnails = browser_api.thumbnails_by_video(video)?;
+            titles.sort_by(|a, b| a.time_submitted.cmp(&b.time_submitted).reverse());
+            thumbnails.sort_by(|a, b| a.time_submitted.cmp(&b.time_submitted).reverse());
 
-                let mut flags = String::new();
+            let info = get_branding_info(options, client, video, &response)?;
 
-                if title.original {
-                    flags.push('o');
-                }
+            let titles = join(&response.titles, &titles, |title| title.uuid.as_str(), |title| &*title.uuid,
+                |served, database| MergedSubmissionRecord::from_title(video.as_str(), served, database));
+            let thumbnails = join(&response.thumbnails, &thumbnails, |thumbnail| thumbnail.uuid.as_str(), |thumbnail| &*thumbnail.uuid,
+                |served, database| MergedSubmissionRecord::from_thumbnail(video.as_str(), served, database));
 
-                if title.removed || title.shadow_hidden {
-                    if title.removed {
-                        flags.push('m'); // Removed by VIP
-                    }
-
-                    if title.shadow_hidden {
-                        flags.push('x'); // Shadowhidden
-                    }
-                } else if title.votes - title.downvotes < -1 {
-                    flags.push('d'); // Removed by downvotes
-                } else if title.votes < 0 {
-                    flags.push('r'); // Replaced by submitter
-                } else if !title.locked && title.score < 0 {
-                    flags.push('h'); // Title should only appear in submission menus
-                }
+            for record in titles.iter().chain(&thumbnails) {
+                let warning = if record.served.is_some() && !record.in_database {
+                    "is served by the main API, but not in DeArrow Browser's database (it may not have caught up yet)"
+                } else if record.served.is_none() && !(record.removed || record.shadow_hidden || record.downvoted) {
+                    "is in DeArrow Browser's database, but not served by the main API"
+                } else {
+                    continue;
+                };
 
-                if title.unverified {
-                    flags.push('u'); // Submitted by unverified user
+                // With --watch, only warn about each submission once instead of on every refresh
+                if watch.as_deref_mut().is_some_and(|watch| !watch.warned.insert(record.uuid.clone())) {
+                    continue;
                 }
 
-                if title.locked {
-                    flags.push('l'); // Locked by a VIP
-                }
+                eprintln!("Warning: {} {} {}", record.kind, record.uuid, warning);
+            }
 
-                if title.vip {
-                    flags.push('v'); // Submitted by VIP
-                }
+            if options.format != OutputFormat::Table {
+                return output::write_video(writer, options.format, &info, Some(&titles), Some(&thumbnails));
+            }
 
-                if !flags.is_empty() {
-                    score.reserve(2 + flags.len());
-                    score.push_str(", ");
-                    score.push_str(&flags);
-                }
+            print_header(&info, writer)?;
+            print_branding_info(&info, writer)?;
 
-                builder.push_record([
-                    DateTime::from_timestamp_millis(title.time_submitted).map_or(title.time_submitted.to_string(), utils::render_datetime),
-                    title.title.to_string(),
-                    score,
-                    title.uuid.to_string(),
-                    if let Some(username) = title.username { format!("\"{}\"", username) } else { String::new() },
-                    title.user_id.to_string(),
-                ]);
-            }
+            let titles_table = merged_table(&titles, "Title", terminal_width, watch.as_deref_mut());
+            let thumbnails_table = merged_table(&thumbnails, "Thumbnail", terminal_width, watch.as_deref_mut());
+            write!(writer, "\n{}\n\n{}\n", titles_table, thumbnails_table)?;
+        },
+    }
 
-            let table_settings = tabled::settings::Settings::default()
-                .with(tabled::settings::Style::psql())
-                .with(tabled::settings::Width::wrap(terminal_width as usize).priority(tabled::settings::peaker::PriorityMax::new(false)))
-                .with(tabled::settings::Width::increase(terminal_width as usize));
+    if let Some(watch) = watch {
+        watch.finish(writer)?;
+    }
+
+    Ok(())
+}
 
-            let mut table = builder.build();
-            table.with(table_settings);
+/// Builds the table of DeArrow Browser titles, in the order they are passed in.
+///
+/// If `with_video_id` is set, a column with the video ID is added after the submission time.
+/// With `watch`, a first column marks the changes since the previous fetch.
+pub fn titles_table(titles: Vec<ApiTitle>, terminal_width: u16, with_video_id: bool, mut watch: Option<&mut Watch>) -> tabled::Table {
+    let titles_len = titles.len();
+    let watching = watch.is_some();
 
-            for i in 0..titles_len {
-                table.modify(tabled::settings::object::Cell::new(i, 4),
-                    tabled::settings::Width::truncate(16).suffix("..."));
-            }
+    let mut builder = tabled::builder::Builder::new();
+    builder.push_record(with_change_column(with_video_id_column(vec!["Submitted", "Title", "Score", "UUID", "Username", "User ID"], "Video ID", with_video_id), "", watching));
 
-            write!(stdout, "\n{}\n", table)?;
-        },
-        SubmissionKind::Thumbnail => {
-            let url = Url::parse(&options.browser_api)?.join("thumbnails/video_id/")?.join(&video)?;
-            let response = make_request(&client, url)?;
+    let mut score_length = 1;
 
-            let mut thumbnails: Vec<ApiThumbnail> = response.json()?;
-            thumbnails.sort_by(|a, b| a.time_submitted.cmp(&b.time_submitted).reverse());
+    for title in &titles {
+        score_length = score_length.max(if title.score == 0 { 1 } else { title.score.abs().ilog10() + 1 + (title.score.is_negative() as u32) })
+            .max(if title.votes == 0 { 1 } else { title.votes.abs().ilog10() + 1 })
+            .max(if title.downvotes == 0 { 1 } else { title.downvotes.abs().ilog10() + 1 });
+    }
 
-            let mut stdout = std::io::stdout();
-            print_header(&client, &video, &mut stdout)?;
+    for title in titles {
+        let score = format_score(format!("{:>width$} ({:>+width$} | {})", title.score, title.votes,
+            if title.downvotes == 0 {
+                format!("{: >width$}-0", "", width = score_length.saturating_sub(2) as usize)
+            } else {
+                format!("{:->width$}", -title.downvotes, width = score_length as usize)
+            }, width = score_length as usize), Flags::of_title(&title));
+        let change = watch.as_deref_mut().map_or("", |watch| watch.mark(&title.uuid, format!("title \"{}\"", title.title), i64::from(title.score), Flags::of_title(&title)));
+
+        builder.push_record(with_change_column(with_video_id_column(vec![
+            DateTime::from_timestamp_millis(title.time_submitted).map_or(title.time_submitted.to_string(), utils::render_datetime),
+            title.title.to_string(),
+            score,
+            title.uuid.to_string(),
+            if let Some(username) = &title.username { format!("\"{}\"", username) } else { String::new() },
+            title.user_id.to_string(),
+        ], title.video_id.to_string(), with_video_id), change.to_owned(), watching));
+    }
 
-            let mut builder = tabled::builder::Builder::new();
-            builder.push_record(["Submitted", "Timestamp", "Score", "UUID", "Username", "User ID"]);
+    let table_settings = tabled::settings::Settings::default()
+        .with(tabled::settings::Style::psql())
+        .with(tabled::settings::Width::wrap(terminal_width as usize).priority(tabled::settings::peaker::PriorityMax::new(false)))
+        .with(tabled::settings::Width::increase(terminal_width as usize));
 
-            let mut score_length = 1;
+    let mut table = builder.build();
+    table.with(table_settings);
 
-            for title in &thumbnails {
-                score_length = score_length.max(if title.score == 0 { 1 } else { title.score.abs().ilog10() + 1 + (title.score.is_negative() as u32) })
-                    .max(if title.votes == 0 { 1 } else { title.votes.abs().ilog10() + 1 })
-                    .max(if title.downvotes == 0 { 1 } else { title.downvotes.abs().ilog10() + 1 });
-            }
+    let username_column = 4 + with_video_id as usize + watching as usize;
 
-            for thumbnail in thumbnails {
-                let mut score = format!("{:>width$} ({:>+width$} | {})", thumbnail.score, thumbnail.votes,
-                    if thumbnail.downvotes == 0 {
-                        format!("{: >width$}-0", "", width = score_length.saturating_sub(2) as usize)
-                    } else {
-                        format!("{:->width$}", -thumbnail.downvotes, width = score_length as usize)
-                    }, width = score_length as usize);
-
-                let mut flags = String::new();
-
-                if thumbnail.removed || thumbnail.shadow_hidden {
-                    if thumbnail.removed {
-                        flags.push('m'); // Removed by VIP
-                    }
-
-                    if thumbnail.shadow_hidden {
-                        flags.push('x'); // Shadowhidden
-                    }
-                } else if thumbnail.votes - thumbnail.downvotes < -1 {
-                    flags.push('d'); // Removed by downvotes
-                } else if !thumbnail.locked {
-                    if (thumbnail.original && thumbnail.score < 1) || thumbnail.score < 0 {
-                        // if original: Thumbnail has insufficient score to be shown (needs >=1 or lock)
-                        // if not:      Thumbnail should only appear in submission menus
-                        flags.push('h');
-                    }
-                }
+    for i in 0..titles_len {
+        table.modify(tabled::settings::object::Cell::new(i, username_column),
+            tabled::settings::Width::truncate(16).suffix("..."));
+    }
 
-                if thumbnail.locked {
-                    flags.push('l'); // Locked by a VIP
-                }
+    table
+}
 
-                if thumbnail.vip {
-                    flags.push('v'); // Submitted by VIP
-                }
+/// Builds the table of DeArrow Browser thumbnails, in the order they are passed in.
+///
+/// If `with_video_id` is set, a column with the video ID is added after the submission time.
+/// With `watch`, a first column marks the changes since the previous fetch.
+pub fn thumbnails_table(thumbnails: Vec<ApiThumbnail>, terminal_width: u16, with_video_id: bool, mut watch: Option<&mut Watch>) -> tabled::Table {
+    let watching = watch.is_some();
 
-                if !flags.is_empty() {
-                    score.reserve(2 + flags.len());
-                    score.push_str(", ");
-                    score.push_str(&flags);
-                }
+    let mut builder = tabled::builder::Builder::new();
+    builder.push_record(with_change_column(with_video_id_column(vec!["Submitted", "Timestamp", "Score", "UUID", "Username", "User ID"], "Video ID", with_video_id), "", watching));
 
-                builder.push_record([
-                    DateTime::from_timestamp_millis(thumbnail.time_submitted).map_or(thumbnail.time_submitted.to_string(), utils::render_datetime),
-                    thumbnail.timestamp.map(|t| t.to_string()).unwrap_or_else(|| if thumbnail.original { String::from("Original") } else { String::from("Unknown") }),
-                    score,
-                    thumbnail.uuid.to_string(),
-                    if let Some(username) = thumbnail.username { format!("\"{}\"", username) } else { String::new() },
-                    thumbnail.user_id.to_string(),
-                ]);
-            }
+    let mut score_length = 1;
 
-            let table_settings = tabled::settings::Settings::default()
-                .with(tabled::settings::Style::psql())
-                .with(tabled::settings::Width::wrap(terminal_width as usize).priority(tabled::settings::peaker::PriorityMax::new(false)))
-                .with(tabled::settings::Width::increase(terminal_width as usize));
+    for title in &thumbnails {
+        score_length = score_length.max(if title.score == 0 { 1 } else { title.score.abs().ilog10() + 1 + (title.score.is_negative() as u32) })
+            .max(if title.votes == 0 { 1 } else { title.votes.abs().ilog10() + 1 })
+            .max(if title.downvotes == 0 { 1 } else { title.downvotes.abs().ilog10() + 1 });
+    }
 
-            let table = builder.build().with(table_settings).to_string();
-            write!(stdout, "\n{}\n", table)?;
-        },
+    for thumbnail in thumbnails {
+        let score = format_score(format!("{:>width$} ({:>+width$} | {})", thumbnail.score, thumbnail.votes,
+            if thumbnail.downvotes == 0 {
+                format!("{: >width$}-0", "", width = score_length.saturating_sub(2) as usize)
+            } else {
+                format!("{:->width$}", -thumbnail.downvotes, width = score_length as usize)
+            }, width = score_length as usize), Flags::of_thumbnail(&thumbnail));
+        let timestamp = thumbnail.timestamp.map(|t| t.to_string()).unwrap_or_else(|| if thumbnail.original { String::from("Original") } else { String::from("Unknown") });
+        let change = watch.as_deref_mut().map_or("", |watch| watch.mark(&thumbnail.uuid, format!("thumbnail {}", timestamp), i64::from(thumbnail.score), Flags::of_thumbnail(&thumbnail)));
+
+        builder.push_record(with_change_column(with_video_id_column(vec![
+            DateTime::from_timestamp_millis(thumbnail.time_submitted).map_or(thumbnail.time_submitted.to_string(), utils::render_datetime),
+            timestamp,
+            score,
+            thumbnail.uuid.to_string(),
+            if let Some(username) = &thumbnail.username { format!("\"{}\"", username) } else { String::new() },
+            thumbnail.user_id.to_string(),
+        ], thumbnail.video_id.to_string(), with_video_id), change.to_owned(), watching));
     }
 
-    Ok(())
+    let table_settings = tabled::settings::Settings::default()
+        .with(tabled::settings::Style::psql())
+        .with(tabled::settings::Width::wrap(terminal_width as usize).priority(tabled::settings::peaker::PriorityMax::new(false)))
+        .with(tabled::settings::Width::increase(terminal_width as usize));
+
+    let mut table = builder.build();
+    table.with(table_settings);
+    table
+}
+
+/// Joins the submissions served by the main API with the ones in DeArrow Browser's database by UUID.
+///
+/// Served submissions come first, in the order of the main API, followed by the other ones in the order passed in.
+fn join<'a, M, B>(served: &'a [M], database: &'a [B], served_uuid: impl Fn(&M) -> &str, database_uuid: impl Fn(&B) -> &str,
+                  record: impl Fn(Option<(usize, &'a M)>, Option<&'a B>) -> MergedSubmissionRecord) -> Vec<MergedSubmissionRecord> {
+    let by_uuid = database.iter().map(|submission| (database_uuid(submission), submission)).collect::<HashMap<_, _>>();
+    let served_uuids = served.iter().map(&served_uuid).collect::<HashSet<_>>();
+
+    served.iter().enumerate()
+        .map(|(i, submission)| record(Some((i + 1, submission)), by_uuid.get(served_uuid(submission)).copied()))
+        .chain(database.iter()
+            .filter(|submission| !served_uuids.contains(database_uuid(submission)))
+            .map(|submission| record(None, Some(submission))))
+        .collect()
+}
+
+/// Builds the table of joined titles or thumbnails for `view <VIDEO_ID> all`.
+///
+/// With `watch`, a first column marks the changes since the previous fetch.
+fn merged_table(records: &[MergedSubmissionRecord], kind_column: &str, terminal_width: u16, mut watch: Option<&mut Watch>) -> tabled::Table {
+    let watching = watch.is_some();
+
+    let mut builder = tabled::builder::Builder::new();
+    builder.push_record(with_change_column(vec!["Served", kind_column, "Votes", "Score", "UUID", "Username", "User ID"], "", watching));
+
+    for record in records {
+        let submission = match (&record.title, record.timestamp) {
+            (Some(title), _) => title.clone(),
+            (None, Some(timestamp)) => timestamp.to_string(),
+            (None, None) if record.original => String::from("Original"),
+            (None, None) => String::from("Unknown"),
+        };
+
+        let score = match (record.score, record.votes, record.downvotes) {
+            (Some(score), Some(votes), Some(downvotes)) => format!("{} (+{} | -{})", score, votes, downvotes),
+            _ => String::from("-"),
+        };
+
+        let label = match &record.title {
+            Some(title) => format!("title \"{}\"", title),
+            None => format!("thumbnail {}", submission),
+        };
+
+        // The main API's score is live, while DeArrow Browser's may lag behind
+        let change = watch.as_deref_mut().map_or("", |watch| watch.mark(&record.uuid, label,
+            record.main_votes.map(i64::from).or(record.score).unwrap_or_default(), record.flag_set()));
+
+        builder.push_record(with_change_column(vec![
+            record.served.map_or(String::from("no"), |position| format!("#{}", position)),
+            submission,
+            record.main_votes.map(|votes| votes.to_string()).unwrap_or_default(),
+            format_score(score, record.flag_set()),
+            record.uuid.clone(),
+            if let Some(username) = &record.username { format!("\"{}\"", username) } else { String::new() },
+            record.user_id.clone(),
+        ], change.to_owned(), watching));
+    }
+
+    let table_settings = tabled::settings::Settings::default()
+        .with(tabled::settings::Style::psql())
+        .with(tabled::settings::Width::wrap(terminal_width as usize).priority(tabled::settings::peaker::PriorityMax::new(false)))
+        .with(tabled::settings::Width::increase(terminal_width as usize));
+
+    let mut table = builder.build();
+    table.with(table_settings);
+
+    for i in 1..=records.len() {
+        table.modify(tabled::settings::object::Cell::new(i, 5 + watching as usize),
+            tabled::settings::Width::truncate(16).suffix("..."));
+    }
+
+    table
+}
+
+fn with_video_id_column<T>(mut record: Vec<T>, video_id: T, with_video_id: bool) -> Vec<T> {
+    if with_video_id {
+        record.insert(1, video_id);
+    }
+
+    record
+}
+
+fn with_change_column<T>(mut record: Vec<T>, change: T, watching: bool) -> Vec<T> {
+    if watching {
+        record.insert(0, change);
+    }
+
+    record
+}
+
+/// Appends the flags to an already formatted score, like `"12, ol"`.
+pub fn format_score(mut score: String, flags: Flags) -> String {
+    if !flags.is_empty() {
+        score.push_str(", ");
+        score.push_str(&flags.to_string());
+    }
+
+    score
+}
+
+/// A submission as compared between fetches with `--watch`.
+#[derive(Clone, Debug)]
+struct WatchedRow {
+    /// Like `title "..."` or `thumbnail 12.5`, to show rows that disappeared.
+    label: String,
+    score: i64,
+    flags: Flags,
+}
+
+/// The rows of the previous fetch with `--watch`, keyed by UUID, to mark what changed.
+#[derive(Default, Debug)]
+pub struct Watch {
+    /// `None` before the first fetch, so that nothing is marked then.
+    previous: Option<HashMap<String, WatchedRow>>,
+    current: HashMap<String, WatchedRow>,
+    /// The UUIDs that a warning was already printed for, kept across fetches.
+    warned: HashSet<String>,
+}
+
+impl Watch {
+    /// Records a row of the current fetch and returns its mark: `+` if it is new, `*` if its score or flags
+    /// (including the lock state) changed.
+    fn mark(&mut self, uuid: &str, label: String, score: i64, flags: Flags) -> &'static str {
+        let mark = match self.previous.as_ref().map(|previous| previous.get(uuid)) {
+            None => "",
+            Some(None) => "+",
+            Some(Some(previous)) if previous.score != score || previous.flags != flags => "*",
+            Some(Some(_)) => "",
+        };
+
+        self.current.insert(uuid.to_owned(), WatchedRow { label, score, flags, });
+        mark
+    }
+
+    /// Writes the rows of the previous fetch that are gone now, and makes the current fetch the previous one.
+    fn finish(&mut self, writer: &mut impl Write) -> anyhow::Result<()> {
+        if let Some(previous) = &self.previous {
+            let mut removed = previous.iter().filter(|(uuid, _)| !self.current.contains_key(*uuid)).collect::<Vec<_>>();
+            removed.sort_by_key(|(uuid, _)| *uuid);
+
+            if !removed.is_empty() {
+                write!(writer, "\nGone since the previous refresh:\n")?;
+
+                for (uuid, row) in removed {
+                    write!(writer, "- {} (score {}, UUID {})\n", row.label, format_score(row.score.to_string(), row.flags), uuid)?;
+                }
+            }
+        }
+
+        self.previous = Some(std::mem::take(&mut self.current));
+        Ok(())
+    }
+
+    /// Forgets the rows of a fetch that failed halfway.
+    fn discard(&mut self) {
+        self.current.clear();
+    }
 }
@@ -14,69 +14,313 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use anyhow::{anyhow, Context};
-use reqwest::Url;
-use std::collections::HashMap;
+use anyhow::{anyhow, bail, Context};
+use dearrow_cli::api::main::{BrandingSubmission, BrandingVote, CasualVote, MainApiClient, MainApiResponse};
+use dearrow_cli::cache::CacheMode;
+use dearrow_cli::VideoId;
 
-use crate::{CasualCategory, Options, ThumbnailSubmission, VoteSubmissionSubcommand};
+use crate::{identity, Options, ThumbnailSubmission, VoteSubmissionSubcommand, utils};
 
-pub fn run(options: Options, client: reqwest::blocking::Client, _terminal_width: u16, kind: VoteSubmissionSubcommand, video: String, downvote: bool, no_autolock: bool, using_casual: bool) -> anyhow::Result<reqwest::blocking::Response> {
-    let private_user_id = std::env::var("SPONSORBLOCK_PRIVATE_USERID").context("Could not get private user ID")?;
-
-    let mut request_data = HashMap::new();
-    request_data.insert("service", serde_json::Value::String(String::from("YouTube")));
-    request_data.insert("userAgent", serde_json::Value::String(String::from(crate::USER_AGENT)));
-    request_data.insert("userID", serde_json::Value::String(String::from(&private_user_id)));
-    request_data.insert("videoID", serde_json::Value::String(video));
-    request_data.insert("downvote", serde_json::Value::Bool(downvote));
+/// The request sent by `vote`.
+enum VoteRequest {
+    Branding(BrandingVote),
+    Casual(CasualVote),
+}
 
-    let is_casual = matches!(&kind, &VoteSubmissionSubcommand::Casual { .. });
+pub fn run(options: Options, client: reqwest::blocking::Client, _terminal_width: u16, kind: Option<VoteSubmissionSubcommand>, uuid: Option<String>, video: VideoId, downvote: bool, no_autolock: bool, using_casual: bool, yes: bool, verify: bool) -> anyhow::Result<()> {
+    let private_user_id = identity::private_user_id(&options)?;
+    // Votes are based on the current data, not what was cached by an earlier `view`
+    let main_api = options.main_api_client(&client)?.with_cache(options.cache.with_mode(CacheMode::Refresh));
 
-    if !is_casual {
-        request_data.insert("autoLock", serde_json::Value::Bool(!no_autolock));
-        request_data.insert("casualMode", serde_json::Value::Bool(using_casual));
-    }
+    let with_options = |request: BrandingVote| BrandingVote {
+        downvote,
+        auto_lock: !no_autolock,
+        casual_mode: using_casual,
+        ..request
+    };
 
-    match kind {
-        VoteSubmissionSubcommand::Title { title, was_warned, } => {
-            request_data.insert("title", serde_json::Value::Object([
-                (String::from("title"), serde_json::Value::String(title)),
-            ].into_iter().collect()));
-            request_data.insert("wasWarned", serde_json::Value::Bool(was_warned));
+    let request = match (kind, uuid) {
+        (Some(_), Some(_)) => bail!("--uuid cannot be used together with a submission kind"),
+        (None, None) => bail!("Either a submission kind (title, thumbnail or casual) or --uuid is required"),
+        (None, Some(uuid)) => {
+            let request = find_by_uuid(&options, &client, &main_api.get_branding(&video)?, private_user_id, &video, &uuid)?;
+            VoteRequest::Branding(with_options(request))
         },
-        VoteSubmissionSubcommand::Thumbnail { thumbnail, } => {
-            match thumbnail {
-                ThumbnailSubmission::Original {} => {
-                    request_data.insert("thumbnail", serde_json::Value::Object([
-                        (String::from("original"), serde_json::Value::Bool(true)),
-                    ].into_iter().collect()));
+        (Some(VoteSubmissionSubcommand::Title { title, pick, was_warned, }), None) => {
+            let title = match (title, pick) {
+                (Some(title), _) => title,
+                (None, Some(pick)) => {
+                    // Use the (possibly cached) data that `view <VIDEO_ID> main` showed, so that the numbers match
+                    let title = pick_title(&options.main_api_client(&client)?.get_branding(&video)?, pick)?;
+                    eprintln!("Picked title #{}: \"{}\"", pick, title);
+                    title
                 },
+                (None, None) => unreachable!("clap requires either a title or --pick"),
+            };
+
+            VoteRequest::Branding(with_options(BrandingVote {
+                was_warned,
+                ..BrandingVote::title(private_user_id, video.to_string(), title)
+            }))
+        },
+        (Some(VoteSubmissionSubcommand::Thumbnail { thumbnail, }), None) => {
+            let timestamp = match thumbnail {
+                ThumbnailSubmission::Original {} => None,
                 ThumbnailSubmission::At { timestamp, } => {
-                    request_data.insert("thumbnail", serde_json::Value::Object([
-                        (String::from("original"), serde_json::Value::Bool(false)),
-                        (String::from("timestamp"), serde_json::Value::Number(serde_json::Number::from_f64(timestamp).ok_or_else(|| anyhow!("Cannot parse timestamp"))?)),
-                    ].into_iter().collect()));
+                    if !timestamp.is_finite() {
+                        bail!("Cannot parse timestamp");
+                    }
+
+                    Some(timestamp)
                 },
-            }
+            };
+
+            VoteRequest::Branding(with_options(BrandingVote::thumbnail(private_user_id, video.to_string(), timestamp)))
         },
-        VoteSubmissionSubcommand::Casual { categories } => {
+        (Some(VoteSubmissionSubcommand::Casual { categories }), None) => {
             if downvote {
-                request_data.insert("categories", serde_json::Value::Array(vec![]));
+                VoteRequest::Casual(CasualVote::downvote(private_user_id, video.to_string()))
             } else {
-                request_data.insert("categories", serde_json::Value::Array(categories.into_iter()
-                    .map(CasualCategory::name).map(String::from).map(serde_json::Value::String).collect()));
+                VoteRequest::Casual(CasualVote::new(private_user_id, video.to_string(), categories))
             }
         },
+    };
+
+    if !yes || options.dry_run {
+        print_effect(&main_api, &video, &request, options.dry_run)?;
+    }
+
+    if options.dry_run {
+        let preview = match &request {
+            VoteRequest::Branding(request) => main_api.preview_branding(request)?,
+            VoteRequest::Casual(request) => main_api.preview_casual(request)?,
+        };
+
+        eprintln!("Not sending request (dry run): {}", preview);
+        return Ok(());
     }
 
-    let url = Url::parse(&options.main_api)?;
-    let url = url.join(if is_casual { "casual" } else { "branding" })?;
+    if !yes {
+        utils::require_confirmation("Send this vote?")?;
+    }
+
+    let before = if verify { Some(main_api.get_branding(&video)?) } else { None };
+
+    let response = match &request {
+        VoteRequest::Branding(request) => main_api.post_branding(request)?,
+        VoteRequest::Casual(request) => main_api.post_casual(request)?,
+    };
 
-    let response = client.post(url)
-        .header("User-Agent", crate::USER_AGENT)
-        .json(&request_data)
-        .send().context("Failed to send branding request")?;
     eprintln!("Sent request. Response: {}", response.status());
 
-    response.error_for_status().context("Server returned error")
+    response.error_for_status().context("Server returned error")?;
+
+    match before {
+        Some(before) => verify_vote(&main_api, &video, &request, &before),
+        None => Ok(()),
+    }
+}
+
+/// Fetches the branding data again after voting and reports how the voted submission changed.
+///
+/// Fails if the vote does not seem to have taken effect, which can happen when the user is shadowbanned,
+/// or when the server ignored the vote (like a repeated vote for the same submission).
+fn verify_vote(main_api: &MainApiClient, video: &VideoId, request: &VoteRequest, before: &MainApiResponse) -> anyhow::Result<()> {
+    let after = main_api.get_branding(video)?;
+
+    let request = match request {
+        VoteRequest::Branding(request) => request,
+        VoteRequest::Casual(_) => {
+            eprintln!("Casual votes now: {}", describe_casual_votes(&after));
+
+            if after.casual_votes == before.casual_votes {
+                bail!("The casual votes on this video did not change");
+            }
+
+            return Ok(());
+        },
+    };
+
+    let (_, target, served_before) = served(before, &request.submission);
+    let (_, _, served_after) = served(&after, &request.submission);
+    let old = served_before.into_iter().find(|submission| submission.description == target);
+    let new = served_after.into_iter().find(|submission| submission.description == target);
+
+    match (old, new) {
+        (Some(_), None) if request.downvote => {
+            eprintln!("{} is not served anymore.", utils::capitalize(&target));
+            Ok(())
+        },
+        (_, None) => bail!("{} is not served by the main API after voting", utils::capitalize(&target)),
+        (Some(old), Some(new)) if old.votes == new.votes && old.locked == new.locked => {
+            bail!("{} did not change ({} votes{}). The vote may have been ignored, for example because you already voted for it \
+                or are shadowbanned", utils::capitalize(&target), new.votes, if new.locked { ", locked" } else { "" })
+        },
+        (old, Some(new)) => {
+            let before = match old {
+                Some(old) => format!("before: {} votes{}", old.votes, if old.locked { ", locked" } else { "" }),
+                None => String::from("new submission"),
+            };
+
+            eprintln!("{} now has {} votes{} ({}).", utils::capitalize(&target), new.votes, if new.locked { ", locked" } else { "" }, before);
+            Ok(())
+        },
+    }
+}
+
+/// A title or thumbnail as served by the main API, as shown before and after voting.
+struct Served {
+    description: String,
+    votes: i32,
+    locked: bool,
+    original: bool,
+}
+
+fn describe_thumbnail(original: bool, timestamp: Option<f64>) -> String {
+    match (original, timestamp) {
+        (true, _) => String::from("the original thumbnail"),
+        (false, Some(timestamp)) => format!("the thumbnail at {}", timestamp),
+        (false, None) => String::from("an unknown thumbnail"),
+    }
+}
+
+/// The kind of `submission`, its description and the titles or thumbnails of the same kind in `branding`.
+fn served(branding: &MainApiResponse, submission: &BrandingSubmission) -> (&'static str, String, Vec<Served>) {
+    match submission {
+        BrandingSubmission::Title { title } => ("title", format!("the title \"{}\"", title), branding.titles.iter().map(|title| Served {
+            description: format!("the title \"{}\"", title.title),
+            votes: title.votes,
+            locked: title.locked,
+            original: title.original,
+        }).collect()),
+        BrandingSubmission::Thumbnail { original, timestamp } => ("thumbnail", describe_thumbnail(*original, *timestamp), branding.thumbnails.iter().map(|thumbnail| Served {
+            description: describe_thumbnail(thumbnail.original, thumbnail.timestamp),
+            votes: thumbnail.votes,
+            locked: thumbnail.locked,
+            original: thumbnail.original,
+        }).collect()),
+    }
+}
+
+fn describe_casual_votes(branding: &MainApiResponse) -> String {
+    if branding.casual_votes.is_empty() {
+        return String::from("none");
+    }
+
+    branding.casual_votes.iter()
+        .map(|vote| format!("{}x {}", vote.count, vote.id.name()))
+        .collect::<Vec<_>>().join(", ")
+}
+
+/// Prints what is currently on the video and what the vote is expected to do, based on the VIP status and auto-lock.
+fn print_effect(main_api: &MainApiClient, video: &VideoId, request: &VoteRequest, dry_run: bool) -> anyhow::Result<()> {
+    let branding = main_api.get_branding(video)?;
+
+    let request = match request {
+        VoteRequest::Branding(request) => request,
+        VoteRequest::Casual(request) => {
+            eprintln!("Current casual votes: {}", describe_casual_votes(&branding));
+
+            if request.downvote {
+                eprintln!("This vote removes your casual votes on this video.");
+            } else {
+                eprintln!("This vote is for the casual categories: {}", request.categories.iter()
+                    .map(|category| category.name()).collect::<Vec<_>>().join(", "));
+            }
+
+            return Ok(());
+        },
+    };
+
+    let (kind, target, served) = served(&branding, &request.submission);
+    let existing = served.iter().find(|submission| submission.description == target);
+    let other_locked = served.iter().find(|submission| submission.locked && submission.description != target);
+
+    match existing {
+        Some(existing) => eprintln!("{} was already submitted ({} votes{}{}).", utils::capitalize(&target), existing.votes,
+            if existing.locked { ", locked" } else { "" }, if existing.original { ", original" } else { "" }),
+        None => eprintln!("{} is not served by the main API (it was never submitted, or it was removed).", utils::capitalize(&target)),
+    }
+
+    if let Some(other_locked) = other_locked {
+        eprintln!("{} is locked.", utils::capitalize(&other_locked.description));
+    }
+
+    // Checking for VIP status sends the private user ID, which a dry run must not do
+    let vip = if dry_run {
+        eprintln!("Not checking whether you are a VIP (dry run), assuming you are not.");
+        false
+    } else {
+        match main_api.is_user_vip(&request.user_id) {
+            Ok(vip) => vip,
+            Err(err) => {
+                eprintln!("Could not check whether you are a VIP, assuming you are not: {:#}", err);
+                false
+            },
+        }
+    };
+
+    let locking = vip && request.auto_lock;
+
+    let effect = match (request.downvote, existing, locking) {
+        (false, Some(_), true) => format!("upvotes and locks {}", target),
+        (false, None, true) => format!("submits and locks {}", target),
+        (false, Some(existing), false) => format!("adds a vote to {} (currently {})", target, existing.votes),
+        (false, None, false) => format!("submits {}", target),
+        (true, Some(_), true) => format!("removes {} immediately", target),
+        (true, Some(existing), false) => format!("removes a vote from {} (currently {})", target, existing.votes),
+        (true, None, _) => format!("probably has no effect, since {} is not served", target),
+    };
+
+    eprintln!("As {}, this vote {}.", if locking { "a VIP with auto-lock" } else if vip { "a VIP without auto-lock" } else { "a normal user" }, effect);
+
+    match (request.downvote, existing, other_locked) {
+        (true, None, _) if !served.is_empty() => eprintln!("Existing {}s: {}.", kind, served.iter()
+            .map(|submission| submission.description.as_str()).collect::<Vec<_>>().join(", ")),
+        (false, _, Some(other_locked)) if !locking => eprintln!("{} stays shown, since it is locked.", utils::capitalize(&other_locked.description)),
+        _ => {},
+    }
+
+    Ok(())
+}
+
+/// Gets the n-th title (starting at 1) in the order shown by `view <VIDEO_ID> main`.
+fn pick_title(branding: &MainApiResponse, pick: usize) -> anyhow::Result<String> {
+    pick.checked_sub(1).and_then(|index| branding.titles.get(index))
+        .map(|title| title.title.clone())
+        .ok_or_else(|| anyhow!("There is no title #{} on this video (it has {} titles)", pick, branding.titles.len()))
+}
+
+/// Creates a vote for the title or thumbnail with the given UUID.
+///
+/// Submissions not served by the main API anymore (like removed ones) are looked up in DeArrow Browser.
+fn find_by_uuid(options: &Options, client: &reqwest::blocking::Client, branding: &MainApiResponse, private_user_id: String, video: &VideoId, uuid: &str) -> anyhow::Result<BrandingVote> {
+    if let Some(title) = branding.titles.iter().find(|title| title.uuid == uuid) {
+        return Ok(BrandingVote::title(private_user_id, video.to_string(), title.title.clone()));
+    }
+
+    if let Some(thumbnail) = branding.thumbnails.iter().find(|thumbnail| thumbnail.uuid == uuid) {
+        return Ok(BrandingVote::thumbnail(private_user_id, video.to_string(), thumbnail_timestamp(thumbnail.original, thumbnail.timestamp, uuid)?));
+    }
+
+    let browser_api = options.browser_api_client(client)?;
+
+    if let Some(title) = browser_api.titles_by_video(video)?.into_iter().find(|title| &*title.uuid == uuid) {
+        return Ok(BrandingVote::title(private_user_id, video.to_string(), title.title.to_string()));
+    }
+
+    if let Some(thumbnail) = browser_api.thumbnails_by_video(video)?.into_iter().find(|thumbnail| &*thumbnail.uuid == uuid) {
+        return Ok(BrandingVote::thumbnail(private_user_id, video.to_string(), thumbnail_timestamp(thumbnail.original, thumbnail.timestamp, uuid)?));
+    }
+
+    bail!("No title or thumbnail with UUID {} found on video {}", uuid, video)
+}
+
+/// `None` stands for the original thumbnail.
+pub fn thumbnail_timestamp(original: bool, timestamp: Option<f64>, uuid: &str) -> anyhow::Result<Option<f64>> {
+    match (original, timestamp) {
+        (true, _) => Ok(None),
+        (false, Some(timestamp)) => Ok(Some(timestamp)),
+        (false, None) => bail!("Thumbnail {} is neither original nor has a timestamp", uuid),
+    }
 }
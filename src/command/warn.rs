@@ -0,0 +1,67 @@
+// dearrow-cli - program to view and vote for DeArrow submissions
+// Copyright (C) 2024  mschae23
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anyhow::Context;
+use dearrow_cli::api::main::{WarningRequest, WarningType};
+use dearrow_cli::cache::CacheMode;
+
+use crate::{identity, Options, WarningKind, utils};
+use crate::command::user;
+
+pub fn run(options: Options, client: reqwest::blocking::Client, terminal_width: u16, user: String, message: Option<String>, revoke: bool, extension: WarningType, yes: bool) -> anyhow::Result<()> {
+    let private_user_id = identity::private_user_id(&options)?;
+
+    let warnings = options.browser_api_client(&client)?.with_cache(options.cache.with_mode(CacheMode::Refresh))
+        .warnings_by_user(&user, WarningKind::Received)?;
+    let active = warnings.into_iter().filter(|warning| warning.active).collect::<Vec<_>>();
+
+    if active.is_empty() {
+        eprintln!("User {} has no active warnings.", user);
+    } else {
+        eprintln!("Active warnings of user {}:\n{}", user, user::warnings_table(active, false, terminal_width));
+    }
+
+    let request = WarningRequest {
+        issuer_user_id: private_user_id,
+        user_id: user,
+        enabled: !revoke,
+        reason: message.unwrap_or_default(),
+        warning_type: extension,
+    };
+
+    let prompt = if revoke {
+        format!("Revoke the active warnings of user {}?", request.user_id)
+    } else {
+        format!("Warn user {} with message \"{}\"?", request.user_id, request.reason)
+    };
+
+    let main_api = options.main_api_client(&client)?;
+
+    if options.dry_run {
+        eprintln!("Not sending request (dry run): {}", main_api.preview_warning(&request)?);
+        return Ok(());
+    }
+
+    if !yes {
+        utils::require_confirmation(&prompt)?;
+    }
+
+    let response = main_api.post_warning(&request)?;
+    eprintln!("Sent request. Response: {}", response.status());
+
+    response.error_for_status().context("Server returned error")?;
+    Ok(())
+}
@@ -0,0 +1,186 @@
+// dearrow-cli - program to view and vote for DeArrow submissions
+// Copyright (C) 2024  mschae23
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use anyhow::{Context, anyhow};
+use serde::Deserialize;
+use dearrow_cli::api::oembed::DEFAULT_OEMBED_API;
+use dearrow_cli::cache::{Cache, CacheMode};
+
+use crate::{identity, OptionArgs, Options, OriginalTitleSource, OutputFormat};
+
+pub const DEFAULT_MAIN_API: &str = "https://sponsor.ajay.app/api/";
+pub const DEFAULT_BROWSER_API: &str = "https://dearrow.minibomba.pro/api/";
+
+/// The contents of the configuration file.
+///
+/// ```toml
+/// # Used when no --profile is given
+/// default_profile = "staging"
+/// # Top-level values apply to every profile
+/// format = "table"
+///
+/// [profiles.staging]
+/// main_api = "http://localhost:8080/api/"
+/// no_autolock = true
+/// ```
+#[derive(Deserialize, Default, Debug)]
+pub struct ConfigFile {
+    pub default_profile: Option<String>,
+    #[serde(default)]
+    pub profiles: HashMap<String, Profile>,
+    #[serde(flatten)]
+    pub defaults: Profile,
+}
+
+/// A set of options that can be selected with `--profile`.
+#[derive(Deserialize, Default, Clone, Debug)]
+pub struct Profile {
+    pub main_api: Option<String>,
+    pub browser_api: Option<String>,
+    pub format: Option<OutputFormat>,
+    /// The private user ID used for voting.
+    pub private_user_id: Option<String>,
+    pub private_id_file: Option<PathBuf>,
+    pub private_id_command: Option<String>,
+    pub no_autolock: Option<bool>,
+    pub using_casual: Option<bool>,
+    pub original_title_provider: Option<OriginalTitleSource>,
+    pub oembed_api: Option<String>,
+    pub invidious_api: Option<String>,
+    pub original_title_file: Option<PathBuf>,
+}
+
+impl Profile {
+    /// Whether any source of the private user ID is set.
+    fn has_private_id(&self) -> bool {
+        self.private_user_id.is_some() || self.private_id_file.is_some() || self.private_id_command.is_some()
+    }
+
+    /// Fills every value not set in `self` with the one from `other`.
+    ///
+    /// The sources of the private user ID are taken as a whole from `self` if it sets any of them,
+    /// so that a `private_id_file` in a profile does not take precedence over `SPONSORBLOCK_PRIVATE_USERID`.
+    fn or(self, other: Profile) -> Profile {
+        let (private_user_id, private_id_file, private_id_command) = if self.has_private_id() {
+            (self.private_user_id, self.private_id_file, self.private_id_command)
+        } else {
+            (other.private_user_id, other.private_id_file, other.private_id_command)
+        };
+
+        Profile {
+            main_api: self.main_api.or(other.main_api),
+            browser_api: self.browser_api.or(other.browser_api),
+            format: self.format.or(other.format),
+            private_user_id,
+            private_id_file,
+            private_id_command,
+            no_autolock: self.no_autolock.or(other.no_autolock),
+            using_casual: self.using_casual.or(other.using_casual),
+            original_title_provider: self.original_title_provider.or(other.original_title_provider),
+            oembed_api: self.oembed_api.or(other.oembed_api),
+            invidious_api: self.invidious_api.or(other.invidious_api),
+            original_title_file: self.original_title_file.or(other.original_title_file),
+        }
+    }
+}
+
+/// The default location of the configuration file, `$XDG_CONFIG_HOME/dearrow-cli/config.toml`.
+pub fn default_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("dearrow-cli").join("config.toml"))
+}
+
+fn read(path: &Path) -> anyhow::Result<ConfigFile> {
+    let contents = std::fs::read_to_string(path).with_context(|| format!("Failed to read config file {}", path.display()))?;
+    let file: ConfigFile = toml::from_str(&contents).with_context(|| format!("Failed to parse config file {}", path.display()))?;
+
+    if file.defaults.private_user_id.is_some() || file.profiles.values().any(|profile| profile.private_user_id.is_some()) {
+        identity::check_private("Config file with private_user_id", path)?;
+    }
+
+    Ok(file)
+}
+
+/// Combines the options from the command line and environment with the ones from the config file.
+///
+/// Command line arguments and environment variables take precedence over the selected profile,
+/// which in turn takes precedence over the top-level values of the config file.
+/// The sources of the private user ID are only taken from the first of these that sets any of them.
+pub fn resolve(args: OptionArgs) -> anyhow::Result<Options> {
+    let file = match &args.config {
+        Some(path) => read(path)?,
+        None => match default_path() {
+            Some(path) if path.exists() => read(&path)?,
+            _ => ConfigFile::default(),
+        },
+    };
+
+    let profile_name = args.profile.or(file.default_profile);
+    let profile = match &profile_name {
+        Some(name) => file.profiles.get(name).cloned()
+            .ok_or_else(|| anyhow!("Profile not found in config file: {}", name))?,
+        None => Profile::default(),
+    };
+
+    let cli = Profile {
+        main_api: args.main_api,
+        browser_api: args.browser_api,
+        format: args.format,
+        private_user_id: std::env::var("SPONSORBLOCK_PRIVATE_USERID").ok(),
+        private_id_file: args.private_id_file,
+        private_id_command: args.private_id_command,
+        no_autolock: None,
+        using_casual: None,
+        original_title_provider: if args.no_original_title { Some(OriginalTitleSource::None) } else { args.original_title_provider },
+        oembed_api: args.oembed_api,
+        invidious_api: args.invidious_api,
+        original_title_file: args.original_title_file,
+    };
+
+    let profile = cli.or(profile).or(file.defaults);
+
+    let cache_mode = if args.no_cache {
+        CacheMode::Disabled
+    } else if args.refresh {
+        CacheMode::Refresh
+    } else {
+        CacheMode::Enabled
+    };
+
+    let cache = match Cache::default_dir() {
+        Some(dir) => Cache::new(dir, cache_mode),
+        None => Cache::disabled(),
+    };
+
+    Ok(Options {
+        main_api: profile.main_api.unwrap_or_else(|| String::from(DEFAULT_MAIN_API)),
+        browser_api: profile.browser_api.unwrap_or_else(|| String::from(DEFAULT_BROWSER_API)),
+        format: profile.format.unwrap_or(OutputFormat::Table),
+        private_user_id: profile.private_user_id,
+        private_id_file: profile.private_id_file,
+        private_id_command: profile.private_id_command,
+        profile: profile_name,
+        no_autolock: profile.no_autolock.unwrap_or(false),
+        using_casual: profile.using_casual.unwrap_or(false),
+        cache,
+        original_title_provider: profile.original_title_provider.unwrap_or(OriginalTitleSource::Oembed),
+        oembed_api: profile.oembed_api.unwrap_or_else(|| String::from(DEFAULT_OEMBED_API)),
+        invidious_api: profile.invidious_api,
+        original_title_file: profile.original_title_file,
+        dry_run: args.dry_run,
+    })
+}
@@ -0,0 +1,172 @@
+// dearrow-cli - program to view and vote for DeArrow submissions
+// Copyright (C) 2024  mschae23
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use dearrow_browser_api::string::{ApiThumbnail, ApiTitle};
+
+use crate::api::main::{MainApiThumbnail, MainApiTitle};
+
+/// The flags shown after the score of a submission.
+///
+/// See the legend in the README for what each of them means.
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+pub struct Flags {
+    /// `o`
+    pub original: bool,
+    /// `m`
+    pub removed: bool,
+    /// `x`
+    pub shadow_hidden: bool,
+    /// `d`
+    pub downvoted: bool,
+    /// `r`
+    pub replaced: bool,
+    /// `h`
+    pub hidden: bool,
+    /// `u`
+    pub unverified: bool,
+    /// `l`
+    pub locked: bool,
+    /// `v`
+    pub vip: bool,
+}
+
+impl Flags {
+    pub fn of_main_title(title: &MainApiTitle) -> Self {
+        Flags {
+            original: title.original,
+            hidden: !title.locked && title.votes < 0, // Title should only appear in submission menus
+            locked: title.locked, // Locked by a VIP
+            ..Default::default()
+        }
+    }
+
+    pub fn of_main_thumbnail(thumbnail: &MainApiThumbnail) -> Self {
+        Flags {
+            original: thumbnail.original,
+            hidden: !thumbnail.locked && thumbnail.votes < 0, // Thumbnail should only appear in submission menus
+            locked: thumbnail.locked, // Locked by a VIP
+            ..Default::default()
+        }
+    }
+
+    pub fn of_title(title: &ApiTitle) -> Self {
+        let mut flags = Flags {
+            original: title.original,
+            unverified: title.unverified, // Submitted by unverified user
+            locked: title.locked, // Locked by a VIP
+            vip: title.vip, // Submitted by VIP
+            ..Default::default()
+        };
+
+        if title.removed || title.shadow_hidden {
+            flags.removed = title.removed; // Removed by VIP
+            flags.shadow_hidden = title.shadow_hidden; // Shadowhidden
+        } else if title.votes - title.downvotes < -1 {
+            flags.downvoted = true; // Removed by downvotes
+        } else if title.votes < 0 {
+            flags.replaced = true; // Replaced by submitter
+        } else if !title.locked && title.score < 0 {
+            flags.hidden = true; // Title should only appear in submission menus
+        }
+
+        flags
+    }
+
+    /// Note that the `o` flag is never set for DeArrow Browser thumbnails,
+    /// since they show "Original" in the timestamp column instead.
+    pub fn of_thumbnail(thumbnail: &ApiThumbnail) -> Self {
+        let mut flags = Flags {
+            locked: thumbnail.locked, // Locked by a VIP
+            vip: thumbnail.vip, // Submitted by VIP
+            ..Default::default()
+        };
+
+        if thumbnail.removed || thumbnail.shadow_hidden {
+            flags.removed = thumbnail.removed; // Removed by VIP
+            flags.shadow_hidden = thumbnail.shadow_hidden; // Shadowhidden
+        } else if thumbnail.votes - thumbnail.downvotes < -1 {
+            flags.downvoted = true; // Removed by downvotes
+        } else if !thumbnail.locked && ((thumbnail.original && thumbnail.score < 1) || thumbnail.score < 0) {
+            // if original: Thumbnail has insufficient score to be shown (needs >=1 or lock)
+            // if not:      Thumbnail should only appear in submission menus
+            flags.hidden = true;
+        }
+
+        flags
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Flags::default()
+    }
+
+    /// Returns whether every flag set in `other` is also set in `self`.
+    pub fn contains(&self, other: Flags) -> bool {
+        (!other.original || self.original) && (!other.removed || self.removed) && (!other.shadow_hidden || self.shadow_hidden)
+            && (!other.downvoted || self.downvoted) && (!other.replaced || self.replaced) && (!other.hidden || self.hidden)
+            && (!other.unverified || self.unverified) && (!other.locked || self.locked) && (!other.vip || self.vip)
+    }
+
+    /// Returns whether any flag set in `other` is also set in `self`.
+    pub fn intersects(&self, other: Flags) -> bool {
+        (other.original && self.original) || (other.removed && self.removed) || (other.shadow_hidden && self.shadow_hidden)
+            || (other.downvoted && self.downvoted) || (other.replaced && self.replaced) || (other.hidden && self.hidden)
+            || (other.unverified && self.unverified) || (other.locked && self.locked) || (other.vip && self.vip)
+    }
+}
+
+/// Parses flags from their letters, like `"ol"`.
+impl FromStr for Flags {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut flags = Flags::default();
+
+        for c in s.chars() {
+            match c {
+                'o' => flags.original = true,
+                'm' => flags.removed = true,
+                'x' => flags.shadow_hidden = true,
+                'd' => flags.downvoted = true,
+                'r' => flags.replaced = true,
+                'h' => flags.hidden = true,
+                'u' => flags.unverified = true,
+                'l' => flags.locked = true,
+                'v' => flags.vip = true,
+                c => return Err(format!("Unknown flag: {} (expected one of omxdrhulv)", c)),
+            }
+        }
+
+        Ok(flags)
+    }
+}
+
+impl Display for Flags {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (set, c) in [
+            (self.original, 'o'), (self.removed, 'm'), (self.shadow_hidden, 'x'),
+            (self.downvoted, 'd'), (self.replaced, 'r'), (self.hidden, 'h'),
+            (self.unverified, 'u'), (self.locked, 'l'), (self.vip, 'v'),
+        ] {
+            if set {
+                write!(f, "{}", c)?;
+            }
+        }
+
+        Ok(())
+    }
+}
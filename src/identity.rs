@@ -0,0 +1,188 @@
+// dearrow-cli - program to view and vote for DeArrow submissions
+// Copyright (C) 2024  mschae23
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use anyhow::{Context, anyhow, bail};
+
+use crate::Options;
+
+/// Name of the keyring service the private user ID is stored under.
+#[cfg(feature = "keyring")]
+const KEYRING_SERVICE: &str = "dearrow-cli";
+
+/// Gets the private user ID used for voting.
+///
+/// The following sources are tried in order:
+/// 1. `--private-id-file`
+/// 2. `--private-id-command`
+/// 3. the `SPONSORBLOCK_PRIVATE_USERID` environment variable
+/// 4. the system keyring (only when built with the `keyring` feature)
+/// 5. the file written by `dearrow-cli login`
+///
+/// The first three can also be set in the config file, but [`config::resolve`](crate::config::resolve) only keeps
+/// the ones from the command line and environment, the selected profile or the top level, whichever sets any of them first.
+pub fn private_user_id(options: &Options) -> anyhow::Result<String> {
+    if let Some(path) = &options.private_id_file {
+        return read_file(path);
+    }
+
+    if let Some(command) = &options.private_id_command {
+        return run_command(command);
+    }
+
+    if let Some(private_user_id) = &options.private_user_id {
+        return Ok(private_user_id.clone());
+    }
+
+    #[cfg(feature = "keyring")]
+    if let Some(private_user_id) = keyring_entry(options)?.get_password().map(Some).or_else(|err| match err {
+        keyring::Error::NoEntry => Ok(None),
+        err => Err(err),
+    }).context("Failed to get private user ID from keyring")? {
+        return Ok(private_user_id);
+    }
+
+    match login_file(options) {
+        Some(path) if path.exists() => read_file(&path),
+        _ => bail!("No private user ID set. Use `dearrow-cli login`, the SPONSORBLOCK_PRIVATE_USERID environment variable, \
+            --private-id-file or --private-id-command"),
+    }
+}
+
+/// Stores the private user ID for future invocations. Returns a description of where it was stored.
+pub fn store(options: &Options, private_user_id: &str) -> anyhow::Result<String> {
+    #[cfg(feature = "keyring")]
+    {
+        keyring_entry(options)?.set_password(private_user_id).context("Failed to store private user ID in keyring")?;
+        Ok(String::from("system keyring"))
+    }
+
+    #[cfg(not(feature = "keyring"))]
+    {
+        let path = login_file(options).ok_or_else(|| anyhow!("Could not determine config directory"))?;
+        write_file(&path, private_user_id)?;
+        Ok(path.display().to_string())
+    }
+}
+
+/// Removes the stored private user ID. Returns `false` if there was none.
+pub fn clear(options: &Options) -> anyhow::Result<bool> {
+    #[cfg(feature = "keyring")]
+    {
+        match keyring_entry(options)?.delete_credential() {
+            Ok(()) => Ok(true),
+            Err(keyring::Error::NoEntry) => Ok(false),
+            Err(err) => Err(err).context("Failed to remove private user ID from keyring"),
+        }
+    }
+
+    #[cfg(not(feature = "keyring"))]
+    {
+        match login_file(options) {
+            Some(path) if path.exists() => {
+                std::fs::remove_file(&path).with_context(|| format!("Failed to remove {}", path.display()))?;
+                Ok(true)
+            },
+            _ => Ok(false),
+        }
+    }
+}
+
+#[cfg(feature = "keyring")]
+fn keyring_entry(options: &Options) -> anyhow::Result<keyring::Entry> {
+    keyring::Entry::new(KEYRING_SERVICE, options.profile.as_deref().unwrap_or("default")).context("Failed to access keyring")
+}
+
+/// `$XDG_CONFIG_HOME/dearrow-cli/private_id`, or `private_id.<PROFILE>` if a profile is selected.
+fn login_file(options: &Options) -> Option<PathBuf> {
+    let name = match &options.profile {
+        Some(profile) => format!("private_id.{}", profile),
+        None => String::from("private_id"),
+    };
+
+    dirs::config_dir().map(|dir| dir.join("dearrow-cli").join(name))
+}
+
+/// Fails if `path` can be accessed by other users. `what` describes the file in the error message.
+pub fn check_private(what: &str, path: &Path) -> anyhow::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mode = std::fs::metadata(path).with_context(|| format!("Failed to read {}", path.display()))?
+            .permissions().mode();
+
+        if mode & 0o077 != 0 {
+            bail!("{} {} can be accessed by other users (mode {:o}). Run `chmod 600` on it first", what, path.display(), mode & 0o777);
+        }
+    }
+
+    #[cfg(not(unix))]
+    let _ = (what, path);
+
+    Ok(())
+}
+
+fn read_file(path: &Path) -> anyhow::Result<String> {
+    check_private("Private ID file", path)?;
+
+    let contents = std::fs::read_to_string(path).with_context(|| format!("Failed to read private ID file {}", path.display()))?;
+    first_line(&contents).with_context(|| format!("Private ID file {} is empty", path.display()))
+}
+
+#[cfg(not(feature = "keyring"))]
+fn write_file(path: &Path, private_user_id: &str) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+
+    let mut open_options = std::fs::OpenOptions::new();
+    open_options.write(true).create(true).truncate(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        open_options.mode(0o600);
+    }
+
+    use std::io::Write;
+    let mut file = open_options.open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    writeln!(file, "{}", private_user_id).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+fn run_command(command: &str) -> anyhow::Result<String> {
+    #[cfg(windows)]
+    let (shell, flag) = ("cmd", "/C");
+    #[cfg(not(windows))]
+    let (shell, flag) = ("sh", "-c");
+
+    let output = Command::new(shell).arg(flag).arg(command)
+        .stdin(std::process::Stdio::inherit())
+        .stderr(std::process::Stdio::inherit())
+        .output().context("Failed to run private ID command")?;
+
+    if !output.status.success() {
+        bail!("Private ID command failed: {}", output.status);
+    }
+
+    let stdout = String::from_utf8(output.stdout).map_err(|_| anyhow!("Private ID command printed invalid UTF-8"))?;
+    first_line(&stdout).context("Private ID command printed nothing")
+}
+
+fn first_line(contents: &str) -> Option<String> {
+    contents.lines().next().map(str::trim).filter(|line| !line.is_empty()).map(String::from)
+}
@@ -0,0 +1,33 @@
+// dearrow-cli - program to view and vote for DeArrow submissions
+// Copyright (C) 2024  mschae23
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Typed clients for the APIs used by DeArrow CLI.
+//!
+//! [`MainApiClient`] talks to a SponsorBlockServer instance (the live DeArrow data and voting),
+//! [`BrowserApiClient`] to the internal API of a DeArrow Browser instance (the database copy).
+
+pub mod api;
+pub mod cache;
+pub mod flags;
+pub mod original_title;
+pub mod video;
+
+pub use api::browser::BrowserApiClient;
+pub use api::main::MainApiClient;
+pub use video::VideoId;
+
+/// The `User-Agent` header sent with every request.
+pub const USER_AGENT: &str = concat!("dearrow-cli/", env!("CARGO_PKG_VERSION"));
@@ -15,21 +15,85 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use std::path::PathBuf;
+use chrono::NaiveDate;
 use clap::{Parser, Args};
-use clap::crate_version;
+use dearrow_cli::flags::Flags;
+use dearrow_cli::cache::Cache;
+use dearrow_cli::api::invidious::InvidiousClient;
+use dearrow_cli::api::oembed::OEmbedClient;
+use dearrow_cli::original_title::{NoOriginalTitle, OriginalTitleLookup, OriginalTitleProvider, OverrideFile};
+use dearrow_cli::{BrowserApiClient, MainApiClient, VideoId};
 
-mod command;
+pub use dearrow_cli::api::main::{CasualCategory, WarningType};
+pub use dearrow_cli::api::browser::WarningKind;
 
-const USER_AGENT: &str = concat!("dearrow-cli/", crate_version!());
+mod command;
+mod config;
+mod identity;
+mod output;
 
 mod utils {
-    use chrono::{DateTime, Utc};
+    use chrono::{DateTime, NaiveDate, Utc};
 
     const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+    const DATE_FORMAT: &str = "%Y-%m-%d";
 
     pub fn render_datetime(dt: DateTime<Utc>) -> String {
         format!("{}", dt.format(TIME_FORMAT))
     }
+
+    pub fn parse_date(s: &str) -> Result<NaiveDate, String> {
+        NaiveDate::parse_from_str(s, DATE_FORMAT).map_err(|err| format!("{} (expected YYYY-MM-DD)", err))
+    }
+
+    /// Parses a non-negative number of seconds, like `1.5`.
+    pub fn parse_seconds(s: &str) -> Result<f64, String> {
+        match s.parse::<f64>() {
+            Ok(seconds) if seconds.is_finite() && seconds >= 0.0 => Ok(seconds),
+            Ok(_) => Err(String::from("must be a non-negative number of seconds")),
+            Err(err) => Err(err.to_string()),
+        }
+    }
+
+    /// Makes the first character of `text` uppercase.
+    pub fn capitalize(text: &str) -> String {
+        let mut chars = text.chars();
+
+        match chars.next() {
+            Some(first) => first.to_uppercase().chain(chars).collect(),
+            None => String::new(),
+        }
+    }
+
+    /// Asks a yes/no question on stderr. Anything other than `y` or `yes` counts as no.
+    pub fn confirm(prompt: &str) -> anyhow::Result<bool> {
+        use std::io::Write;
+
+        eprint!("{} [y/N] ", prompt);
+        std::io::stderr().flush()?;
+
+        let mut buf = String::new();
+        std::io::stdin().read_line(&mut buf)?;
+        Ok(matches!(buf.trim().to_lowercase().as_str(), "y" | "yes"))
+    }
+
+    /// Asks for confirmation before sending a request, for commands that can skip this with `--yes`.
+    ///
+    /// Fails if the user declines, or if standard input is not a terminal (like in scripts), so that the program
+    /// exits with an error status when nothing was sent.
+    pub fn require_confirmation(prompt: &str) -> anyhow::Result<()> {
+        use std::io::IsTerminal;
+
+        if !std::io::stdin().is_terminal() {
+            anyhow::bail!("Cannot ask for confirmation, since standard input is not a terminal. Pass --yes to send the request without asking");
+        }
+
+        if !confirm(prompt)? {
+            anyhow::bail!("Aborted");
+        }
+
+        Ok(())
+    }
 }
 
 /// A CLI program to view and vote for DeArrow submissions.
@@ -40,25 +104,207 @@ pub struct Config {
     #[command(subcommand)]
     pub verb: Verb,
     #[command(flatten)]
-    pub options: Options,
+    pub options: OptionArgs,
 }
 
 #[derive(Args)]
-pub struct Options {
+pub struct OptionArgs {
     /// The URI base for the main voting and query commands. It has to be compatible
     /// with the DeArrow API (SponsorBlockServer).
     ///
     /// The value provided will be concatenated with the API path to form the final URI used.
     /// Note that the trailing slash is significant.
-    #[arg(long, default_value = "https://sponsor.ajay.app/api/")]
-    pub main_api: String,
+    ///
+    /// [default: https://sponsor.ajay.app/api/]
+    #[arg(long, env = "DEARROW_CLI_MAIN_API")]
+    pub main_api: Option<String>,
     /// The URI base of the API used for the database browser commands. It has to be
     /// compatible with the internal API of DeArrowBrowser.
     ///
     /// The value provided will be concatenated with the API path to form the final URI used.
     /// Note that the trailing slash is significant.
-    #[arg(long, default_value = "https://dearrow.minibomba.pro/api/")]
+    ///
+    /// [default: https://dearrow.minibomba.pro/api/]
+    #[arg(long, env = "DEARROW_CLI_BROWSER_API")]
+    pub browser_api: Option<String>,
+    /// The format to print query results in.
+    ///
+    /// `table` is meant to be read by humans. The other formats contain the same data as
+    /// structured records, including the computed flags, which makes them easier to parse from scripts.
+    ///
+    /// [default: table]
+    #[arg(long, value_enum, env = "DEARROW_CLI_FORMAT")]
+    pub format: Option<OutputFormat>,
+    /// Path to the configuration file.
+    ///
+    /// If not set, `$XDG_CONFIG_HOME/dearrow-cli/config.toml` is used if it exists.
+    #[arg(long, env = "DEARROW_CLI_CONFIG", value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+    pub config: Option<PathBuf>,
+    /// The profile from the configuration file to use.
+    ///
+    /// If not set, the `default_profile` from the configuration file is used, if any.
+    #[arg(long, short, env = "DEARROW_CLI_PROFILE")]
+    pub profile: Option<String>,
+    /// Read the private user ID used for voting from this file.
+    ///
+    /// Only the first line is used. On Unix, the file must not be accessible by other users.
+    #[arg(long, env = "DEARROW_CLI_PRIVATE_ID_FILE", value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+    pub private_id_file: Option<PathBuf>,
+    /// Run this shell command to get the private user ID used for voting, like `pass show sponsorblock`.
+    ///
+    /// The first line of its output is used.
+    #[arg(long, env = "DEARROW_CLI_PRIVATE_ID_COMMAND", value_name = "COMMAND", value_hint = clap::ValueHint::CommandString)]
+    pub private_id_command: Option<String>,
+    /// Do not read or write the response cache in `$XDG_CACHE_HOME/dearrow-cli`.
+    #[arg(long, env = "DEARROW_CLI_NO_CACHE")]
+    pub no_cache: bool,
+    /// Ignore cached responses, but cache the new ones. `--no-cache` takes precedence over this.
+    #[arg(long, env = "DEARROW_CLI_REFRESH")]
+    pub refresh: bool,
+    /// Where to look up the original title of videos.
+    ///
+    /// [default: oembed]
+    #[arg(long, value_enum, env = "DEARROW_CLI_ORIGINAL_TITLE_PROVIDER", value_name = "PROVIDER")]
+    pub original_title_provider: Option<OriginalTitleSource>,
+    /// The oEmbed endpoint used to look up original titles. It has to be compatible with YouTube's.
+    ///
+    /// Unlike the other API options, this is the full URI of the endpoint.
+    ///
+    /// [default: https://www.youtube-nocookie.com/oembed]
+    #[arg(long, env = "DEARROW_CLI_OEMBED_API")]
+    pub oembed_api: Option<String>,
+    /// The URI base of an Invidious instance, used to look up original titles with `--original-title-provider invidious`.
+    ///
+    /// The value provided will be concatenated with the API path to form the final URI used.
+    /// Note that the trailing slash is significant.
+    #[arg(long, env = "DEARROW_CLI_INVIDIOUS_API")]
+    pub invidious_api: Option<String>,
+    /// A CSV file with `video` and `title` columns, whose titles are used instead of looking them up.
+    ///
+    /// Videos not in the file are still looked up with the `--original-title-provider`.
+    #[arg(long, env = "DEARROW_CLI_ORIGINAL_TITLE_FILE", value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+    pub original_title_file: Option<PathBuf>,
+    /// Do not look up the original title of videos. Same as `--original-title-provider none`.
+    ///
+    /// No original title is shown then, not even the one submitted to DeArrow.
+    #[arg(long, env = "DEARROW_CLI_NO_ORIGINAL_TITLE", conflicts_with = "original_title_provider")]
+    pub no_original_title: bool,
+    /// Print the requests of `vote`, `batch`, `review`, `warn`, `ban` and `unban` instead of sending them.
+    ///
+    /// The private user ID is redacted from the printed requests; the public user ID derived from it is shown instead.
+    /// Read-only requests that do not contain the private user ID are still sent.
+    #[arg(long, env = "DEARROW_CLI_DRY_RUN")]
+    pub dry_run: bool,
+}
+
+/// The options from the command line, environment and configuration file, combined.
+pub struct Options {
+    pub main_api: String,
     pub browser_api: String,
+    pub format: OutputFormat,
+    /// Use [`identity::private_user_id`] to get the private user ID from any source.
+    pub private_user_id: Option<String>,
+    pub private_id_file: Option<PathBuf>,
+    pub private_id_command: Option<String>,
+    /// The name of the selected profile.
+    pub profile: Option<String>,
+    /// Default for the `--no-autolock` flag of `vote`, `review` and `batch`, unless `--autolock` is passed.
+    pub no_autolock: bool,
+    /// Default for the `--using-casual` flag of `vote`, `review` and `batch`, unless `--not-casual` is passed.
+    pub using_casual: bool,
+    pub cache: Cache,
+    pub original_title_provider: OriginalTitleSource,
+    pub oembed_api: String,
+    /// Only required for [`OriginalTitleSource::Invidious`].
+    pub invidious_api: Option<String>,
+    pub original_title_file: Option<PathBuf>,
+    /// Print requests that would change data on the server instead of sending them.
+    pub dry_run: bool,
+}
+
+impl Options {
+    pub fn main_api_client(&self, client: &reqwest::blocking::Client) -> anyhow::Result<MainApiClient> {
+        Ok(MainApiClient::new(client.clone(), &self.main_api)?.with_cache(self.cache.clone()))
+    }
+
+    pub fn browser_api_client(&self, client: &reqwest::blocking::Client) -> anyhow::Result<BrowserApiClient> {
+        Ok(BrowserApiClient::new(client.clone(), &self.browser_api)?.with_cache(self.cache.clone()))
+    }
+
+    pub fn original_titles(&self, client: &reqwest::blocking::Client) -> anyhow::Result<OriginalTitleLookup> {
+        let provider: Box<dyn OriginalTitleProvider> = match self.original_title_provider {
+            OriginalTitleSource::Oembed => Box::new(OEmbedClient::new(client.clone(), &self.oembed_api)?.with_cache(self.cache.clone())),
+            OriginalTitleSource::Invidious => {
+                let base = self.invidious_api.as_deref().ok_or_else(|| anyhow::anyhow!("--original-title-provider invidious requires --invidious-api"))?;
+                Box::new(InvidiousClient::new(client.clone(), base)?.with_cache(self.cache.clone()))
+            },
+            OriginalTitleSource::None => Box::new(NoOriginalTitle),
+        };
+
+        let provider: Box<dyn OriginalTitleProvider> = match &self.original_title_file {
+            Some(path) => Box::new(OverrideFile::read(path, provider)?),
+            None => provider,
+        };
+
+        Ok(OriginalTitleLookup::new(provider))
+    }
+}
+
+#[derive(clap::ValueEnum, serde::Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// Human-readable tables.
+    #[value()]
+    Table,
+    /// A single JSON document.
+    #[value()]
+    Json,
+    /// One JSON object per line.
+    #[value()]
+    Ndjson,
+    /// Comma-separated values with a header row. Only contains the submissions or warnings, not general video information.
+    #[value()]
+    Csv,
+}
+
+/// Where to look up the original title of videos.
+#[derive(clap::ValueEnum, serde::Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OriginalTitleSource {
+    /// YouTube's oEmbed endpoint, or the one set with `--oembed-api`.
+    #[value()]
+    Oembed,
+    /// The Invidious instance set with `--invidious-api`.
+    #[value()]
+    Invidious,
+    /// Do not look up original titles.
+    #[value()]
+    None,
+}
+
+/// The format of the input file of `batch`.
+#[derive(clap::ValueEnum, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum InputFormat {
+    /// Comma-separated values with a header row.
+    #[value()]
+    Csv,
+    /// One JSON object per line.
+    #[value()]
+    Jsonl,
+}
+
+/// What `batch` does with a row if the video already has a different locked title or thumbnail.
+#[derive(clap::ValueEnum, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OnLocked {
+    /// Skip the row.
+    #[value()]
+    Skip,
+    /// Ask for confirmation.
+    #[value()]
+    Ask,
+    /// Vote anyway, which replaces the locked submission.
+    #[value()]
+    Override,
 }
 
 #[derive(clap::Subcommand)]
@@ -68,12 +314,18 @@ pub enum Verb {
     /// This supports both upvotes and downvotes for titles and thumbnails.
     #[command()]
     Vote {
-        /// ID of the video to vote for a submission on.
+        /// ID or URL of the video to vote for a submission on.
         #[arg(value_name = "VIDEO_ID")]
-        video: String,
+        video: VideoId,
         /// The kind of submission (title or thumbnail).
         #[command(subcommand)]
-        kind: VoteSubmissionSubcommand,
+        kind: Option<VoteSubmissionSubcommand>,
+        /// Vote for the title or thumbnail with this UUID instead of specifying the submission kind.
+        ///
+        /// The UUID is looked up in the branding data of the video (or DeArrow Browser, if the submission
+        /// is not served anymore), and it is an error if it does not belong to this video.
+        #[arg(long, value_name = "UUID")]
+        uuid: Option<String>,
         /// When set, downvotes instead of upvoting.
         #[arg(long, short)]
         downvote: bool,
@@ -84,23 +336,75 @@ pub enum Verb {
         /// - A new submission is not locked by default
         /// - Voting for an existing submission will increment its score, but not lock it
         /// - Downvoting an existing submission will decrement its score, but not immediately remove it
-        #[arg(long, short = 'n', help = "When set, disables auto-lock (only has an effect for VIP users)", long_help = "When set, disables auto-lock (only has an effect for VIP users).\n\nDisabling auto-vote makes the vote count like it would coming from a normal user, which means:\n- A new submission is not locked by default\n- Voting for an existing submission will increment its score, but not lock it\n- Downvoting an existing submission will decrement its score, but not immediately remove it")]
+        #[arg(long, short = 'n', env = "DEARROW_CLI_NO_AUTOLOCK", help = "When set, disables auto-lock (only has an effect for VIP users)", long_help = "When set, disables auto-lock (only has an effect for VIP users).\n\nDisabling auto-vote makes the vote count like it would coming from a normal user, which means:\n- A new submission is not locked by default\n- Voting for an existing submission will increment its score, but not lock it\n- Downvoting an existing submission will decrement its score, but not immediately remove it")]
         no_autolock: bool,
+        /// Enable auto-lock, even if `--no-autolock`, `DEARROW_CLI_NO_AUTOLOCK` or the configuration file disables it.
+        #[arg(long)]
+        autolock: bool,
         /// When set, indicates that the user has [casual mode] enabled.
         ///
         /// [casual mode]: https://wiki.sponsor.ajay.app/w/DeArrow/Casual_mode
+        #[arg(long, env = "DEARROW_CLI_USING_CASUAL")]
+        using_casual: bool,
+        /// Indicate that the user does not have casual mode enabled, even if `--using-casual`,
+        /// `DEARROW_CLI_USING_CASUAL` or the configuration file says so.
+        #[arg(long)]
+        not_casual: bool,
+        /// Do not show the current state of the video and ask for confirmation before voting.
+        #[arg(long, short)]
+        yes: bool,
+        /// After voting, fetch the branding data again and report the new score and lock state of the submission.
+        ///
+        /// Fails if the submission is missing or unchanged afterwards.
+        #[arg(long, env = "DEARROW_CLI_VERIFY")]
+        verify: bool,
+    },
+    /// Review the submissions on a video in a full-screen terminal UI.
+    ///
+    /// Lists the titles and thumbnails served by the main API, like `view <VIDEO_ID> main`. Use the arrow keys (or j/k)
+    /// to select a row and Tab to switch between titles and thumbnails, then press u to upvote, d to downvote or l to
+    /// lock it. t submits a new title. The data is refreshed after every vote.
+    #[command()]
+    Review {
+        /// ID or URL of the video to review.
+        #[arg(value_name = "VIDEO_ID")]
+        video: VideoId,
+        /// When set, upvotes and new titles do not lock the submission (only has an effect for VIP users).
+        ///
+        /// Locking explicitly with l is still possible.
+        #[arg(long, short = 'n', env = "DEARROW_CLI_NO_AUTOLOCK")]
+        no_autolock: bool,
+        /// Enable auto-lock, even if `--no-autolock`, `DEARROW_CLI_NO_AUTOLOCK` or the configuration file disables it.
         #[arg(long)]
+        autolock: bool,
+        /// When set, indicates that the user has [casual mode] enabled.
+        ///
+        /// [casual mode]: https://wiki.sponsor.ajay.app/w/DeArrow/Casual_mode
+        #[arg(long, env = "DEARROW_CLI_USING_CASUAL")]
         using_casual: bool,
+        /// Indicate that the user does not have casual mode enabled, even if `--using-casual`,
+        /// `DEARROW_CLI_USING_CASUAL` or the configuration file says so.
+        #[arg(long)]
+        not_casual: bool,
     },
     /// View DeArrow submissions on a video.
     #[command()]
     View {
-        /// ID of the video to view submissions for.
+        /// ID or URL of the video to view submissions for.
         #[arg(value_name = "VIDEO_ID")]
-        video: String,
+        video: VideoId,
         /// The kind of submissions to show.
         #[arg(value_enum)]
         kind: SubmissionKind,
+        /// Fetch the submissions again periodically and mark the ones that changed, until interrupted.
+        ///
+        /// New submissions are marked with `+`, submissions whose score, lock state or flags changed with `*`.
+        /// Submissions that disappeared are listed below the tables. Only supports table output.
+        #[arg(long)]
+        watch: bool,
+        /// How often to fetch the submissions with `--watch`, in seconds.
+        #[arg(long, value_name = "SECONDS", default_value_t = 30, value_parser = clap::value_parser!(u64).range(1..), requires = "watch")]
+        interval: u64,
     },
     /// View information about a specific user.
     #[command()]
@@ -112,27 +416,193 @@ pub enum Verb {
         #[command(subcommand)]
         subcommand: UserSubcommand,
     },
-    // TODO Warn command
-    //      Maybe ban, too
-    #[command(hide = true)]
+    /// Store your private user ID for future votes.
+    ///
+    /// It is stored in the system keyring if DeArrow CLI was built with the `keyring` feature,
+    /// and in a file only readable by you otherwise. If a profile is selected, it is stored for that profile only.
+    #[command()]
+    Login {
+    },
+    /// Remove the private user ID stored with `login`.
+    #[command()]
+    Logout {
+    },
+    /// Issue or revoke a tip (internally called warning) for a user. Only available to VIP users.
+    ///
+    /// The user's currently active warnings are shown before asking for confirmation.
+    #[command()]
+    Warn {
+        /// The public ID of the user to warn.
+        #[arg(value_name = "USER_ID")]
+        user: String,
+        /// The message shown to the user.
+        #[arg(long, short, required_unless_present = "revoke")]
+        message: Option<String>,
+        /// Revoke the user's active warnings instead of issuing a new one.
+        #[arg(long, conflicts_with = "message")]
+        revoke: bool,
+        /// The extension the warning applies to.
+        #[arg(long, value_enum, default_value = "dearrow")]
+        extension: WarningType,
+        /// Do not ask for confirmation.
+        #[arg(long, short)]
+        yes: bool,
+    },
+    /// Shadowban a user. Only available to VIP users.
+    ///
+    /// Shows how many of the user's submissions would be affected before asking for confirmation.
+    #[command()]
+    Ban {
+        #[command(flatten)]
+        args: BanArgs,
+    },
+    /// Remove a user's shadowban. Only available to VIP users.
+    ///
+    /// Shows how many of the user's submissions would be affected before asking for confirmation.
+    #[command()]
+    Unban {
+        #[command(flatten)]
+        args: BanArgs,
+    },
+    /// Show or clear the response cache.
+    ///
+    /// Responses are cached in `$XDG_CACHE_HOME/dearrow-cli` for 7 days (original titles), 5 minutes (branding data
+    /// from the main API) or an hour (DeArrow Browser data).
+    #[command()]
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+    /// Submit or vote for many titles, thumbnails and casual categories from a file.
+    ///
+    /// The file is read in CSV (with a header row) or JSON lines format, with one submission per row.
+    /// See the manual page or README for the available columns.
+    #[command()]
     Batch {
-        #[arg(value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
-        input: PathBuf,
-        /// When set, disables auto-lock (only has an effect for VIP users).
-        ///
-        /// Disabling auto-vote makes the vote count like it would coming from a normal user,
-        /// which means:
-        /// - A new submission is not locked by default
-        /// - Voting for an existing submission will increment its score, but not lock it
-        /// - Downvoting an existing submission will decrement its score, but not immediately remove it
-        #[arg(long, short = 'n', help = "When set, disables auto-lock (only has an effect for VIP users)", long_help = "When set, disables auto-lock (only has an effect for VIP users).\n\nDisabling auto-vote makes the vote count like it would coming from a normal user, which means:\n- A new submission is not locked by default\n- Voting for an existing submission will increment its score, but not lock it\n- Downvoting an existing submission will decrement its score, but not immediately remove it")]
-        no_autolock: bool,
-        /// When set, requests to the DeArrow server will be printed instead of sent.
-        #[arg(short, long)]
-        simulate: bool,
+        #[command(flatten)]
+        args: BatchArgs,
     },
 }
 
+#[derive(clap::Subcommand)]
+pub enum CacheAction {
+    /// Remove all cached responses.
+    #[command()]
+    Clear {
+    },
+    /// Show the number and size of cached responses.
+    #[command()]
+    Stats {
+    },
+}
+
+#[derive(Args)]
+pub struct BatchArgs {
+    /// The input file.
+    #[arg(value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+    pub input: PathBuf,
+    /// The format of the input file.
+    ///
+    /// By default, files ending in `.jsonl` or `.ndjson` are read as JSON lines, and everything else as CSV.
+    #[arg(long, value_enum)]
+    pub input_format: Option<InputFormat>,
+    /// Prompt for a new title for every row instead of reading the submissions from the file.
+    ///
+    /// In this mode, the input file is a CSV file with a header row. The first column is the video ID,
+    /// and the second column is shown before the prompt (for example, the current title).
+    /// An empty answer skips the row.
+    #[arg(long, short)]
+    pub interactive: bool,
+    /// Only check the input file for problems, without sending any votes.
+    ///
+    /// Besides invalid rows, this reports duplicate rows for the same video and submission kind, titles identical to
+    /// the original title of the video, and titles for videos that already have a locked title. Exits with a non-zero
+    /// status if there are any problems.
+    #[arg(long, conflicts_with = "interactive")]
+    pub check: bool,
+    /// What to do with a title or thumbnail upvote if the video already has a different locked one.
+    ///
+    /// With auto-lock enabled, a vote by a VIP user replaces the submission another VIP locked. The locked submission
+    /// is shown before applying this policy. Downvotes and votes with auto-lock disabled are never affected.
+    #[arg(long, value_enum, default_value_t = OnLocked::Skip)]
+    pub on_locked: OnLocked,
+    /// Downvote instead of upvoting, for rows that leave the `downvote` column empty.
+    #[arg(long, short)]
+    pub downvote: bool,
+    /// Disable auto-lock (only has an effect for VIP users), for rows that leave the `autolock` column empty.
+    ///
+    /// Disabling auto-vote makes the vote count like it would coming from a normal user,
+    /// which means:
+    /// - A new submission is not locked by default
+    /// - Voting for an existing submission will increment its score, but not lock it
+    /// - Downvoting an existing submission will decrement its score, but not immediately remove it
+    #[arg(long, short = 'n', env = "DEARROW_CLI_NO_AUTOLOCK", help = "Disable auto-lock (only has an effect for VIP users), for rows that leave the `autolock` column empty", long_help = "Disable auto-lock (only has an effect for VIP users), for rows that leave the `autolock` column empty.\n\nDisabling auto-vote makes the vote count like it would coming from a normal user, which means:\n- A new submission is not locked by default\n- Voting for an existing submission will increment its score, but not lock it\n- Downvoting an existing submission will decrement its score, but not immediately remove it")]
+    pub no_autolock: bool,
+    /// Enable auto-lock for rows that leave the `autolock` column empty, even if `--no-autolock`,
+    /// `DEARROW_CLI_NO_AUTOLOCK` or the configuration file disables it.
+    #[arg(long)]
+    pub autolock: bool,
+    /// When set, indicates that the user has [casual mode] enabled.
+    ///
+    /// [casual mode]: https://wiki.sponsor.ajay.app/w/DeArrow/Casual_mode
+    #[arg(long, env = "DEARROW_CLI_USING_CASUAL")]
+    pub using_casual: bool,
+    /// Indicate that the user does not have casual mode enabled, even if `--using-casual`,
+    /// `DEARROW_CLI_USING_CASUAL` or the configuration file says so.
+    #[arg(long)]
+    pub not_casual: bool,
+    /// Report title submissions as having been auto-warned, for rows that leave the `was_warned` column empty.
+    #[arg(long)]
+    pub was_warned: bool,
+    /// When set, requests to the DeArrow server will be printed instead of sent. Same as the global `--dry-run`.
+    #[arg(short, long)]
+    pub simulate: bool,
+    /// The journal file, which records the result of every row so that an interrupted run can be resumed.
+    ///
+    /// [default: <FILE>.journal]
+    #[arg(long, value_name = "JOURNAL", value_hint = clap::ValueHint::FilePath)]
+    pub journal: Option<PathBuf>,
+    /// Continue a previous run, skipping rows that were already sent successfully according to the journal.
+    ///
+    /// Without this option, an existing journal file is an error.
+    #[arg(long)]
+    pub resume: bool,
+    /// Wait at least this many seconds between two requests.
+    #[arg(long, value_name = "SECONDS", value_parser = utils::parse_seconds)]
+    pub min_interval: Option<f64>,
+    /// Send at most this many requests per hour, pausing when the limit is reached.
+    ///
+    /// Requests recorded in the journal of a resumed run count towards the limit.
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    pub max_per_hour: Option<u32>,
+    /// Write the result of every row to this CSV file.
+    ///
+    /// Each record contains the line number, video ID, the original title, the submitted value, the HTTP status,
+    /// the server's error response and the time the row was processed. With `--resume`, records are appended to an
+    /// existing report.
+    #[arg(long, value_name = "REPORT", value_hint = clap::ValueHint::FilePath)]
+    pub report: Option<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct BanArgs {
+    /// The public ID of the user.
+    #[arg(value_name = "USER_ID")]
+    pub user: String,
+    /// Do not hide (or unhide) the user's existing titles.
+    #[arg(long)]
+    pub keep_titles: bool,
+    /// Do not hide (or unhide) the user's existing thumbnails.
+    #[arg(long)]
+    pub keep_thumbnails: bool,
+    /// Do not hide (or unhide) the user's existing SponsorBlock segments.
+    #[arg(long)]
+    pub keep_segments: bool,
+    /// Do not ask for confirmation.
+    #[arg(long, short)]
+    pub yes: bool,
+}
+
 #[derive(clap::ValueEnum, Copy, Clone, Debug, PartialEq, Eq)]
 pub enum SubmissionKind {
     /// View title submissions using DeArrowBrowser's internal API.
@@ -144,6 +614,9 @@ pub enum SubmissionKind {
     /// View titles and thumbnails from a live SponsorBlockServer instance.
     #[value()]
     Main,
+    /// View titles and thumbnails from both the main API and DeArrowBrowser, joined by UUID.
+    #[value()]
+    All,
 }
 
 #[derive(clap::Subcommand)]
@@ -157,8 +630,11 @@ pub enum VoteSubmissionSubcommand {
         #[arg(long, help = "Whether to report this title submission as having been auto-warned", long_help = "Whether to report this title submission as having been auto-warned.\n\nThis is intended to log potentially low-quality submissions to DeArrow moderators.")]
         was_warned: bool,
         /// The title to vote for.
-        #[arg()]
-        title: String,
+        #[arg(required_unless_present = "pick")]
+        title: Option<String>,
+        /// Vote for the n-th title (starting at 1) shown by `view <VIDEO_ID> main` instead of typing it.
+        #[arg(long, value_name = "N", conflicts_with = "title")]
+        pick: Option<usize>,
     },
     /// Vote for a thumbnail submission.
     #[command()]
@@ -174,37 +650,6 @@ pub enum VoteSubmissionSubcommand {
     },
 }
 
-#[derive(clap::ValueEnum, serde::Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
-pub enum CasualCategory {
-    #[value()]
-    #[serde(rename = "funny")]
-    Funny,
-    #[value()]
-    #[serde(rename = "creative")]
-    Creative,
-    #[value()]
-    #[serde(rename = "clever")]
-    Clever,
-    #[value()]
-    #[serde(rename = "descriptive")]
-    Descriptive,
-    #[value()]
-    #[serde(rename = "other")]
-    Other,
-}
-
-impl CasualCategory {
-    pub fn name(self) -> &'static str {
-        match self {
-            CasualCategory::Funny => "funny",
-            CasualCategory::Creative => "creative",
-            CasualCategory::Clever => "clever",
-            CasualCategory::Descriptive => "descriptive",
-            CasualCategory::Other => "other",
-        }
-    }
-}
-
 #[derive(clap::Subcommand)]
 pub enum ThumbnailSubmission {
     /// The original thumbnail chosen by the video's uploader.
@@ -234,17 +679,40 @@ pub enum UserSubcommand {
         #[arg(long, short = 'n', default_value = "0")]
         newest: usize,
     },
-    // TODO Submissions
+    /// View titles or thumbnails submitted by this user.
+    ///
+    /// Submissions are shown newest first, with the same score and flags as `view`.
+    #[command()]
+    Submissions {
+        /// The kind of submissions to show.
+        #[arg(value_enum)]
+        kind: UserSubmissionKind,
+        /// Only show the newest n submissions (after filtering). Set to `0` to show all.
+        #[arg(long, short = 'n', default_value = "0")]
+        newest: usize,
+        /// Only show submissions made on or after this date (YYYY-MM-DD, in UTC).
+        #[arg(long, value_name = "DATE", value_parser = utils::parse_date)]
+        since: Option<NaiveDate>,
+        /// Only show submissions made on or before this date (YYYY-MM-DD, in UTC).
+        #[arg(long, value_name = "DATE", value_parser = utils::parse_date)]
+        until: Option<NaiveDate>,
+        /// Only show submissions that have all of these flags, like `lv`.
+        #[arg(long, value_name = "FLAGS")]
+        flags: Option<Flags>,
+        /// Hide submissions that have any of these flags, like `mx`.
+        #[arg(long, value_name = "FLAGS")]
+        exclude_flags: Option<Flags>,
+    },
 }
 
 #[derive(clap::ValueEnum, Copy, Clone, Debug, PartialEq, Eq)]
-pub enum WarningKind {
-    /// View warnings issued by this user.
+pub enum UserSubmissionKind {
+    /// View title submissions using DeArrowBrowser's internal API.
     #[value()]
-    Issued,
-    /// View warnings received by this user.
+    Titles,
+    /// View thumbnail submissions using DeArrowBrowser's internal API.
     #[value()]
-    Received,
+    Thumbnails,
 }
 
 #[derive(clap::ValueEnum, Copy, Clone, Debug, PartialEq, Eq)]
@@ -257,36 +725,43 @@ pub enum Source {
     Browser,
 }
 
-impl WarningKind {
-    pub fn name(self: Self) -> &'static str {
-        match self {
-            Self::Issued => "issued",
-            Self::Received => "received",
-        }
-    }
-}
-
-#[derive(serde::Deserialize)]
-struct OEmbedResponse {
-    title: Option<String>,
-}
-
 fn main() -> anyhow::Result<()> {
     let config = Config::parse();
+    let options = config::resolve(config.options)?;
 
     let client = reqwest::blocking::Client::new();
     let terminal_width = termsize::get().map(|size| size.cols).unwrap_or(120);
 
     match config.verb {
-        Verb::Vote { kind, video, downvote, no_autolock, using_casual } => {
-            command::vote::run(config.options, client, terminal_width, kind, video, downvote, no_autolock, using_casual)?;
-            Ok(())
+        Verb::Vote { kind, video, uuid, downvote, no_autolock, autolock, using_casual, not_casual, yes, verify } => {
+            let (no_autolock, using_casual) = (!autolock && (no_autolock || options.no_autolock), !not_casual && (using_casual || options.using_casual));
+            command::vote::run(options, client, terminal_width, kind, uuid, video, downvote, no_autolock, using_casual, yes, verify)
         },
-        Verb::View { video, kind } =>
-            command::view::run(config.options, client, terminal_width, video, kind),
+        Verb::Review { video, no_autolock, autolock, using_casual, not_casual } => {
+            let (no_autolock, using_casual) = (!autolock && (no_autolock || options.no_autolock), !not_casual && (using_casual || options.using_casual));
+            command::review::run(options, client, video, no_autolock, using_casual)
+        },
+        Verb::View { video, kind, watch, interval } =>
+            command::view::run(options, client, terminal_width, video, kind, watch.then_some(interval)),
         Verb::User { user, subcommand } =>
-            command::user::run(config.options, client, terminal_width, user, subcommand),
-        Verb::Batch { input, no_autolock, simulate } =>
-            command::batch::run(config.options, client, terminal_width, input, no_autolock, simulate),
+            command::user::run(options, client, terminal_width, user, subcommand),
+        Verb::Warn { user, message, revoke, extension, yes } =>
+            command::warn::run(options, client, terminal_width, user, message, revoke, extension, yes),
+        Verb::Ban { args } =>
+            command::ban::run(options, client, args, true),
+        Verb::Unban { args } =>
+            command::ban::run(options, client, args, false),
+        Verb::Login {} =>
+            command::login::run_login(options),
+        Verb::Logout {} =>
+            command::login::run_logout(options),
+        Verb::Cache { action } =>
+            command::cache::run(options, terminal_width, action),
+        Verb::Batch { mut args } => {
+            args.no_autolock = !args.autolock && (args.no_autolock || options.no_autolock);
+            args.using_casual = !args.not_casual && (args.using_casual || options.using_casual);
+            args.simulate |= options.dry_run;
+            command::batch::run(options, client, terminal_width, args)
+        },
     }
 }
@@ -0,0 +1,170 @@
+// dearrow-cli - program to view and vote for DeArrow submissions
+// Copyright (C) 2024  mschae23
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Looking up the original title of a video, which is shown next to the DeArrow submissions.
+//!
+//! Original titles can come from any [`OriginalTitleProvider`]: YouTube's oEmbed endpoint
+//! ([`OEmbedClient`](crate::api::oembed::OEmbedClient)), an Invidious instance
+//! ([`InvidiousClient`](crate::api::invidious::InvidiousClient)), a local [`OverrideFile`], or [`NoOriginalTitle`].
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::path::Path;
+use anyhow::Context;
+use serde::Deserialize;
+
+use crate::video::VideoId;
+
+/// A source of original titles.
+pub trait OriginalTitleProvider: Debug {
+    /// Gets the current title of a video. Returns `None` if this provider does not look up titles at all.
+    fn original_title(&self, video: &VideoId) -> anyhow::Result<Option<String>>;
+}
+
+/// A provider that never looks up any titles.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct NoOriginalTitle;
+
+impl OriginalTitleProvider for NoOriginalTitle {
+    fn original_title(&self, _video: &VideoId) -> anyhow::Result<Option<String>> {
+        Ok(None)
+    }
+}
+
+#[derive(Deserialize)]
+struct OverrideRow {
+    video: String,
+    title: String,
+}
+
+/// Original titles from a local CSV file with `video` and `title` columns.
+///
+/// Videos not in the file are looked up with another provider.
+#[derive(Debug)]
+pub struct OverrideFile {
+    titles: HashMap<VideoId, String>,
+    fallback: Box<dyn OriginalTitleProvider>,
+}
+
+impl OverrideFile {
+    /// Reads the file at `path`. The `video` column can also contain YouTube URLs.
+    pub fn read(path: &Path, fallback: Box<dyn OriginalTitleProvider>) -> anyhow::Result<Self> {
+        let mut reader = csv::Reader::from_path(path).with_context(|| format!("Failed to open original title file {}", path.display()))?;
+        let headers = reader.headers().with_context(|| format!("Failed to read original title file {}", path.display()))?.clone();
+        let mut titles = HashMap::new();
+
+        for record in reader.records() {
+            let record = record.with_context(|| format!("Failed to read original title file {}", path.display()))?;
+            let line = record.position().map_or(0, |position| position.line());
+            let row: OverrideRow = record.deserialize(Some(&headers))
+                .with_context(|| format!("Invalid row on line {} of {}", line, path.display()))?;
+            let video = row.video.parse::<VideoId>()
+                .with_context(|| format!("Invalid video ID on line {} of {}", line, path.display()))?;
+            titles.insert(video, row.title);
+        }
+
+        Ok(OverrideFile {
+            titles,
+            fallback,
+        })
+    }
+}
+
+impl OriginalTitleProvider for OverrideFile {
+    fn original_title(&self, video: &VideoId) -> anyhow::Result<Option<String>> {
+        match self.titles.get(video) {
+            Some(title) => Ok(Some(title.clone())),
+            None => self.fallback.original_title(video),
+        }
+    }
+}
+
+/// The result of looking up the original title of a video.
+///
+/// Failing to get the original title is never an error, since it is only shown for reference.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OriginalTitle {
+    /// The current title of the video, from the provider.
+    Found(String),
+    /// The original title submitted to DeArrow, used because the lookup failed.
+    Fallback {
+        title: String,
+        /// Why the lookup failed.
+        reason: String,
+    },
+    /// The lookup failed and no original title is known from DeArrow data.
+    Unavailable(String),
+    /// The provider does not look up titles, like [`NoOriginalTitle`].
+    Skipped,
+}
+
+impl OriginalTitle {
+    pub fn title(&self) -> Option<&str> {
+        match self {
+            OriginalTitle::Found(title) | OriginalTitle::Fallback { title, .. } => Some(title),
+            OriginalTitle::Unavailable(_) | OriginalTitle::Skipped => None,
+        }
+    }
+
+    pub fn into_title(self) -> Option<String> {
+        match self {
+            OriginalTitle::Found(title) | OriginalTitle::Fallback { title, .. } => Some(title),
+            OriginalTitle::Unavailable(_) | OriginalTitle::Skipped => None,
+        }
+    }
+
+    /// Why the title is not from the provider. `None` if it is, or if the lookup was skipped.
+    pub fn note(&self) -> Option<String> {
+        match self {
+            OriginalTitle::Found(_) | OriginalTitle::Skipped => None,
+            OriginalTitle::Fallback { reason, .. } => Some(format!("from DeArrow data, since the lookup failed: {}", reason)),
+            OriginalTitle::Unavailable(reason) => Some(format!("unavailable: {}", reason)),
+        }
+    }
+}
+
+/// Looks up original titles with a provider, falling back to DeArrow data if that fails.
+#[derive(Debug)]
+pub struct OriginalTitleLookup {
+    provider: Box<dyn OriginalTitleProvider>,
+}
+
+impl OriginalTitleLookup {
+    pub fn new(provider: Box<dyn OriginalTitleProvider>) -> Self {
+        OriginalTitleLookup {
+            provider,
+        }
+    }
+
+    /// Gets the original title of a video.
+    ///
+    /// If the lookup fails, `fallback` is called to get the original title from DeArrow data (the title with
+    /// `original: true`), so that it is only requested when needed.
+    pub fn get(&self, video: &VideoId, fallback: impl FnOnce() -> Option<String>) -> OriginalTitle {
+        match self.provider.original_title(video) {
+            Ok(Some(title)) => OriginalTitle::Found(title),
+            Ok(None) => OriginalTitle::Skipped,
+            Err(err) => {
+                let reason = format!("{:#}", err);
+
+                match fallback() {
+                    Some(title) => OriginalTitle::Fallback { title, reason, },
+                    None => OriginalTitle::Unavailable(reason),
+                }
+            },
+        }
+    }
+}
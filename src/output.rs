@@ -0,0 +1,436 @@
+// dearrow-cli - program to view and vote for DeArrow submissions
+// Copyright (C) 2024  mschae23
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Records for the structured output formats (`--format json|ndjson|csv`).
+//!
+//! The submission records are kept flat (no nested structs), so that every format can write them, including CSV.
+
+use std::io::Write;
+use anyhow::Context;
+use dearrow_browser_api::string::{ApiThumbnail, ApiTitle, ApiWarning, Extension};
+use serde::Serialize;
+
+use dearrow_cli::api::main::{MainApiCasualVote, MainApiThumbnail, MainApiTitle};
+use dearrow_cli::flags::Flags;
+
+use crate::OutputFormat;
+
+/// General information about a video, printed above the submission tables.
+#[derive(Serialize, Clone, Debug)]
+pub struct VideoInfo {
+    pub video_id: String,
+    pub original_title: Option<String>,
+    /// Why `original_title` is missing or not the current title on YouTube.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub original_title_note: Option<String>,
+    pub video_duration: Option<f64>,
+    pub random_time: Option<f64>,
+    /// `random_time` multiplied by `video_duration`.
+    pub random_timestamp: Option<f64>,
+    pub casual_votes: Vec<CasualVoteRecord>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct CasualVoteRecord {
+    pub category: crate::CasualCategory,
+    pub count: i32,
+}
+
+impl From<&MainApiCasualVote> for CasualVoteRecord {
+    fn from(vote: &MainApiCasualVote) -> Self {
+        CasualVoteRecord {
+            category: vote.id,
+            count: vote.count,
+        }
+    }
+}
+
+/// A title or thumbnail as served by a SponsorBlockServer instance.
+#[derive(Serialize, Clone, Debug)]
+pub struct MainSubmissionRecord {
+    /// Either `title` or `thumbnail`.
+    pub kind: &'static str,
+    pub video_id: String,
+    pub title: Option<String>,
+    pub timestamp: Option<f64>,
+    pub original: bool,
+    pub votes: i32,
+    pub uuid: String,
+    pub user_id: String,
+    pub flags: String,
+    pub hidden: bool,
+    pub locked: bool,
+}
+
+impl MainSubmissionRecord {
+    pub fn from_title(video_id: &str, title: &MainApiTitle) -> Self {
+        let flags = Flags::of_main_title(title);
+
+        MainSubmissionRecord {
+            kind: "title",
+            video_id: video_id.to_owned(),
+            title: Some(title.title.clone()),
+            timestamp: None,
+            original: title.original,
+            votes: title.votes,
+            uuid: title.uuid.clone(),
+            user_id: title.user_id.clone(),
+            flags: flags.to_string(),
+            hidden: flags.hidden,
+            locked: flags.locked,
+        }
+    }
+
+    pub fn from_thumbnail(video_id: &str, thumbnail: &MainApiThumbnail) -> Self {
+        let flags = Flags::of_main_thumbnail(thumbnail);
+
+        MainSubmissionRecord {
+            kind: "thumbnail",
+            video_id: video_id.to_owned(),
+            title: None,
+            timestamp: thumbnail.timestamp,
+            original: thumbnail.original,
+            votes: thumbnail.votes,
+            uuid: thumbnail.uuid.clone(),
+            user_id: thumbnail.user_id.clone(),
+            flags: flags.to_string(),
+            hidden: flags.hidden,
+            locked: flags.locked,
+        }
+    }
+}
+
+/// A title or thumbnail as stored in DeArrow Browser's copy of the database.
+#[derive(Serialize, Clone, Debug)]
+pub struct SubmissionRecord {
+    /// Either `title` or `thumbnail`.
+    pub kind: &'static str,
+    pub video_id: String,
+    /// Submission time in milliseconds since the Unix epoch.
+    pub time_submitted: i64,
+    pub title: Option<String>,
+    pub timestamp: Option<f64>,
+    pub original: bool,
+    pub score: i64,
+    pub votes: i64,
+    pub downvotes: i64,
+    pub uuid: String,
+    pub username: Option<String>,
+    pub user_id: String,
+    pub flags: String,
+    pub removed: bool,
+    pub shadow_hidden: bool,
+    pub downvoted: bool,
+    pub replaced: bool,
+    pub hidden: bool,
+    pub unverified: bool,
+    pub locked: bool,
+    pub vip: bool,
+}
+
+impl SubmissionRecord {
+    pub fn from_title(title: &ApiTitle) -> Self {
+        let flags = Flags::of_title(title);
+
+        SubmissionRecord {
+            kind: "title",
+            video_id: title.video_id.to_string(),
+            time_submitted: title.time_submitted,
+            title: Some(title.title.to_string()),
+            timestamp: None,
+            original: title.original,
+            score: i64::from(title.score),
+            votes: i64::from(title.votes),
+            downvotes: i64::from(title.downvotes),
+            uuid: title.uuid.to_string(),
+            username: title.username.as_ref().map(|username| username.to_string()),
+            user_id: title.user_id.to_string(),
+            flags: flags.to_string(),
+            removed: flags.removed,
+            shadow_hidden: flags.shadow_hidden,
+            downvoted: flags.downvoted,
+            replaced: flags.replaced,
+            hidden: flags.hidden,
+            unverified: flags.unverified,
+            locked: flags.locked,
+            vip: flags.vip,
+        }
+    }
+
+    pub fn from_thumbnail(thumbnail: &ApiThumbnail) -> Self {
+        let flags = Flags::of_thumbnail(thumbnail);
+
+        SubmissionRecord {
+            kind: "thumbnail",
+            video_id: thumbnail.video_id.to_string(),
+            time_submitted: thumbnail.time_submitted,
+            title: None,
+            timestamp: thumbnail.timestamp,
+            original: thumbnail.original,
+            score: i64::from(thumbnail.score),
+            votes: i64::from(thumbnail.votes),
+            downvotes: i64::from(thumbnail.downvotes),
+            uuid: thumbnail.uuid.to_string(),
+            username: thumbnail.username.as_ref().map(|username| username.to_string()),
+            user_id: thumbnail.user_id.to_string(),
+            flags: flags.to_string(),
+            removed: flags.removed,
+            shadow_hidden: flags.shadow_hidden,
+            downvoted: flags.downvoted,
+            replaced: flags.replaced,
+            hidden: flags.hidden,
+            unverified: flags.unverified,
+            locked: flags.locked,
+            vip: flags.vip,
+        }
+    }
+}
+
+/// A title or thumbnail joined from the main API and DeArrow Browser by UUID (`view <VIDEO_ID> all`).
+#[derive(Serialize, Clone, Debug)]
+pub struct MergedSubmissionRecord {
+    /// Either `title` or `thumbnail`.
+    pub kind: &'static str,
+    pub video_id: String,
+    pub uuid: String,
+    /// The position in the main API response, starting at 1. `None` if the submission is not served.
+    pub served: Option<usize>,
+    /// Whether the submission is in DeArrow Browser's copy of the database.
+    pub in_database: bool,
+    pub title: Option<String>,
+    pub timestamp: Option<f64>,
+    pub original: bool,
+    /// The score according to the main API.
+    pub main_votes: Option<i32>,
+    /// The score according to DeArrow Browser.
+    pub score: Option<i64>,
+    pub votes: Option<i64>,
+    pub downvotes: Option<i64>,
+    /// Submission time in milliseconds since the Unix epoch, only known from DeArrow Browser.
+    pub time_submitted: Option<i64>,
+    pub username: Option<String>,
+    pub user_id: String,
+    /// The flags computed from DeArrow Browser's data if available, otherwise from the main API's.
+    pub flags: String,
+    pub removed: bool,
+    pub shadow_hidden: bool,
+    pub downvoted: bool,
+    pub replaced: bool,
+    pub hidden: bool,
+    pub unverified: bool,
+    pub locked: bool,
+    pub vip: bool,
+}
+
+impl MergedSubmissionRecord {
+    /// The flags as a set, like they were computed from the submission.
+    pub fn flag_set(&self) -> Flags {
+        Flags {
+            original: self.original,
+            removed: self.removed,
+            shadow_hidden: self.shadow_hidden,
+            downvoted: self.downvoted,
+            replaced: self.replaced,
+            hidden: self.hidden,
+            unverified: self.unverified,
+            locked: self.locked,
+            vip: self.vip,
+        }
+    }
+
+    /// At least one of `served` (with its position) and `database` has to be set.
+    pub fn from_title(video_id: &str, served: Option<(usize, &MainApiTitle)>, database: Option<&ApiTitle>) -> Self {
+        let flags = match (database, served) {
+            (Some(title), _) => Flags::of_title(title),
+            (None, Some((_, title))) => Flags::of_main_title(title),
+            (None, None) => Flags::default(),
+        };
+
+        MergedSubmissionRecord {
+            kind: "title",
+            video_id: video_id.to_owned(),
+            uuid: served.map(|(_, title)| title.uuid.clone()).or_else(|| database.map(|title| title.uuid.to_string())).unwrap_or_default(),
+            served: served.map(|(position, _)| position),
+            in_database: database.is_some(),
+            title: served.map(|(_, title)| title.title.clone()).or_else(|| database.map(|title| title.title.to_string())),
+            timestamp: None,
+            original: flags.original,
+            main_votes: served.map(|(_, title)| title.votes),
+            score: database.map(|title| i64::from(title.score)),
+            votes: database.map(|title| i64::from(title.votes)),
+            downvotes: database.map(|title| i64::from(title.downvotes)),
+            time_submitted: database.map(|title| title.time_submitted),
+            username: database.and_then(|title| title.username.as_ref()).map(|username| username.to_string()),
+            user_id: served.map(|(_, title)| title.user_id.clone()).or_else(|| database.map(|title| title.user_id.to_string())).unwrap_or_default(),
+            flags: flags.to_string(),
+            removed: flags.removed,
+            shadow_hidden: flags.shadow_hidden,
+            downvoted: flags.downvoted,
+            replaced: flags.replaced,
+            hidden: flags.hidden,
+            unverified: flags.unverified,
+            locked: flags.locked,
+            vip: flags.vip,
+        }
+    }
+
+    /// At least one of `served` (with its position) and `database` has to be set.
+    pub fn from_thumbnail(video_id: &str, served: Option<(usize, &MainApiThumbnail)>, database: Option<&ApiThumbnail>) -> Self {
+        let flags = match (database, served) {
+            // DeArrow Browser flags never contain `o`, since that is shown in the timestamp column
+            (Some(thumbnail), _) => Flags { original: thumbnail.original, ..Flags::of_thumbnail(thumbnail) },
+            (None, Some((_, thumbnail))) => Flags::of_main_thumbnail(thumbnail),
+            (None, None) => Flags::default(),
+        };
+
+        MergedSubmissionRecord {
+            kind: "thumbnail",
+            video_id: video_id.to_owned(),
+            uuid: served.map(|(_, thumbnail)| thumbnail.uuid.clone()).or_else(|| database.map(|thumbnail| thumbnail.uuid.to_string())).unwrap_or_default(),
+            served: served.map(|(position, _)| position),
+            in_database: database.is_some(),
+            title: None,
+            timestamp: served.and_then(|(_, thumbnail)| thumbnail.timestamp).or_else(|| database.and_then(|thumbnail| thumbnail.timestamp)),
+            original: flags.original,
+            main_votes: served.map(|(_, thumbnail)| thumbnail.votes),
+            score: database.map(|thumbnail| i64::from(thumbnail.score)),
+            votes: database.map(|thumbnail| i64::from(thumbnail.votes)),
+            downvotes: database.map(|thumbnail| i64::from(thumbnail.downvotes)),
+            time_submitted: database.map(|thumbnail| thumbnail.time_submitted),
+            username: database.and_then(|thumbnail| thumbnail.username.as_ref()).map(|username| username.to_string()),
+            user_id: served.map(|(_, thumbnail)| thumbnail.user_id.clone()).or_else(|| database.map(|thumbnail| thumbnail.user_id.to_string())).unwrap_or_default(),
+            flags: flags.to_string(),
+            removed: flags.removed,
+            shadow_hidden: flags.shadow_hidden,
+            downvoted: flags.downvoted,
+            replaced: flags.replaced,
+            hidden: flags.hidden,
+            unverified: flags.unverified,
+            locked: flags.locked,
+            vip: flags.vip,
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct WarningRecord {
+    /// Issue time in milliseconds since the Unix epoch.
+    pub time_issued: i64,
+    /// Either `sponsorblock` or `dearrow`.
+    pub extension: &'static str,
+    pub active: bool,
+    pub message: String,
+    pub warned_user_id: String,
+    pub issuer_user_id: String,
+}
+
+impl From<&ApiWarning> for WarningRecord {
+    fn from(warning: &ApiWarning) -> Self {
+        WarningRecord {
+            time_issued: warning.time_issued,
+            extension: match warning.extension { Extension::SponsorBlock => "sponsorblock", Extension::DeArrow => "dearrow", },
+            active: warning.active,
+            message: warning.message.to_string(),
+            warned_user_id: warning.warned_user_id.to_string(),
+            issuer_user_id: warning.issuer_user_id.to_string(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct VideoDocument<'a, T> {
+    #[serde(flatten)]
+    video: &'a VideoInfo,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    titles: Option<&'a [T]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thumbnails: Option<&'a [T]>,
+}
+
+#[derive(Serialize)]
+struct VideoLine<'a> {
+    kind: &'static str,
+    #[serde(flatten)]
+    video: &'a VideoInfo,
+}
+
+/// Writes information about a video and its submissions in one of the structured output formats.
+///
+/// - `json` writes a single object containing the video information and the `titles` and `thumbnails` arrays.
+/// - `ndjson` writes the video information (with `"kind": "video"`) on the first line, followed by one submission per line.
+/// - `csv` writes only the submissions, one per row.
+pub fn write_video<T: Serialize>(writer: &mut impl Write, format: OutputFormat, video: &VideoInfo,
+                                 titles: Option<&[T]>, thumbnails: Option<&[T]>) -> anyhow::Result<()> {
+    match format {
+        OutputFormat::Table => unreachable!("table output is handled by each command"),
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut *writer, &VideoDocument { video, titles, thumbnails, })
+                .context("Failed to write JSON output")?;
+            writeln!(writer)?;
+        },
+        OutputFormat::Ndjson => {
+            serde_json::to_writer(&mut *writer, &VideoLine { kind: "video", video, }).context("Failed to write JSON output")?;
+            writeln!(writer)?;
+
+            for record in titles.into_iter().flatten().chain(thumbnails.into_iter().flatten()) {
+                serde_json::to_writer(&mut *writer, record).context("Failed to write JSON output")?;
+                writeln!(writer)?;
+            }
+        },
+        OutputFormat::Csv => {
+            write_csv(writer, titles.into_iter().flatten().chain(thumbnails.into_iter().flatten()))?;
+        },
+    }
+
+    Ok(())
+}
+
+/// Writes a list of records in one of the structured output formats.
+///
+/// - `json` writes a single array.
+/// - `ndjson` writes one record per line.
+/// - `csv` writes one record per row.
+pub fn write_list<T: Serialize>(writer: &mut impl Write, format: OutputFormat, records: &[T]) -> anyhow::Result<()> {
+    match format {
+        OutputFormat::Table => unreachable!("table output is handled by each command"),
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut *writer, records).context("Failed to write JSON output")?;
+            writeln!(writer)?;
+        },
+        OutputFormat::Ndjson => {
+            for record in records {
+                serde_json::to_writer(&mut *writer, record).context("Failed to write JSON output")?;
+                writeln!(writer)?;
+            }
+        },
+        OutputFormat::Csv => {
+            write_csv(writer, records)?;
+        },
+    }
+
+    Ok(())
+}
+
+fn write_csv<'a, T: Serialize + 'a>(writer: &mut impl Write, records: impl IntoIterator<Item = &'a T>) -> anyhow::Result<()> {
+    let mut writer = csv::Writer::from_writer(writer);
+
+    for record in records {
+        writer.serialize(record).context("Failed to write CSV output")?;
+    }
+
+    writer.flush().context("Failed to write CSV output")?;
+    Ok(())
+}
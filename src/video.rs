@@ -0,0 +1,149 @@
+// dearrow-cli - program to view and vote for DeArrow submissions
+// Copyright (C) 2024  mschae23
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Parsing of YouTube video IDs and URLs.
+
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use anyhow::{anyhow, bail};
+use reqwest::Url;
+
+/// A validated YouTube video ID: exactly 11 characters out of `A-Z`, `a-z`, `0-9`, `_` and `-`.
+///
+/// Parsing also accepts video URLs, like `https://www.youtube.com/watch?v=ID&t=42`, `https://youtu.be/ID`,
+/// or `/shorts/`, `/embed/` and `/live/` links (including on `music.youtube.com`). Everything except the ID,
+/// like timestamps or playlist parameters, is ignored.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct VideoId(String);
+
+impl VideoId {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns whether `id` has the format of a video ID. This does not check that the video exists.
+    pub fn is_valid(id: &str) -> bool {
+        id.len() == 11 && id.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-')
+    }
+
+    fn from_url(url: &Url) -> anyhow::Result<Self> {
+        let host = url.host_str().unwrap_or_default();
+        let host = host.strip_prefix("www.").or_else(|| host.strip_prefix("m.")).unwrap_or(host);
+        let mut segments = url.path_segments().into_iter().flatten().filter(|segment| !segment.is_empty());
+
+        let id = match host {
+            "youtu.be" => segments.next().map(str::to_owned),
+            "youtube.com" | "music.youtube.com" | "youtube-nocookie.com" => match segments.next() {
+                Some("watch") => url.query_pairs().find(|(key, _)| key == "v").map(|(_, value)| value.into_owned()),
+                Some("shorts" | "embed" | "live" | "v") => segments.next().map(str::to_owned),
+                _ => None,
+            },
+            _ => bail!("Not a YouTube URL: {}", url),
+        };
+
+        let id = id.ok_or_else(|| anyhow!("URL does not contain a video ID: {}", url))?;
+
+        if !Self::is_valid(&id) {
+            bail!("Invalid video ID in URL: {}", id);
+        }
+
+        Ok(VideoId(id))
+    }
+}
+
+impl FromStr for VideoId {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+
+        if Self::is_valid(s) {
+            return Ok(VideoId(s.to_owned()));
+        }
+
+        if !s.contains('/') {
+            bail!("Invalid video ID: {} (expected 11 characters out of A-Z, a-z, 0-9, _ and -, or a YouTube URL)", s);
+        }
+
+        // Also accept URLs without a scheme, like "youtu.be/ID"
+        let url = if s.contains("://") { Url::parse(s) } else { Url::parse(&format!("https://{}", s)) }
+            .map_err(|err| anyhow!("Invalid video URL: {} ({})", s, err))?;
+        Self::from_url(&url)
+    }
+}
+
+impl Display for VideoId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for VideoId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> anyhow::Result<String> {
+        s.parse::<VideoId>().map(|id| id.0)
+    }
+
+    #[test]
+    fn accepts_ids_and_urls() {
+        for input in [
+            "dQw4w9WgXcQ",
+            "  dQw4w9WgXcQ\n",
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+            "https://www.youtube.com/watch?list=PL123&v=dQw4w9WgXcQ&t=42s",
+            "https://m.youtube.com/watch?v=dQw4w9WgXcQ",
+            "https://music.youtube.com/watch?v=dQw4w9WgXcQ&si=abc",
+            "https://youtu.be/dQw4w9WgXcQ?t=10",
+            "youtu.be/dQw4w9WgXcQ",
+            "www.youtube.com/watch?v=dQw4w9WgXcQ",
+            "https://www.youtube.com/shorts/dQw4w9WgXcQ",
+            "https://www.youtube.com/embed/dQw4w9WgXcQ",
+            "https://www.youtube.com/live/dQw4w9WgXcQ?feature=share",
+            "https://www.youtube-nocookie.com/embed/dQw4w9WgXcQ",
+            "https://www.youtube.com/v/dQw4w9WgXcQ",
+        ] {
+            assert_eq!(parse(input).unwrap(), "dQw4w9WgXcQ", "input: {:?}", input);
+        }
+
+        assert_eq!(parse("a-b_c-d_e-f").unwrap(), "a-b_c-d_e-f");
+    }
+
+    #[test]
+    fn rejects_invalid_ids_and_urls() {
+        for input in [
+            "",
+            "dQw4w9WgXc",
+            "dQw4w9WgXcQQ",
+            "dQw4w9WgXc!",
+            "https://vimeo.com/dQw4w9WgXcQ",
+            "https://www.youtube.com/watch?list=PL123",
+            "https://www.youtube.com/channel/dQw4w9WgXcQ",
+            "https://youtu.be/",
+            "https://youtu.be/dQw4w9WgXc",
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQQ",
+        ] {
+            assert!(parse(input).is_err(), "input: {:?}", input);
+        }
+    }
+}